@@ -6,7 +6,7 @@ function __saveall {
 	NBTOT=$(tmux display-message -p '#{session_windows}')
 	for (( w=0; w<$NBTOT; w++ ))
 	do  
-		__savewin $w
+		__savewin $w "$@"
 		echo -e "\n\n"
 	done
 	echo "Done"
@@ -42,15 +42,16 @@ function __savewin {
 	tmux select-window -t $PREVWIN
 
 	echo "Editing window $WINDOW_NAME"
-	tmuxp_session_creator edit -n "$SESSION_NAME" -i "$NWIN" -l "$WINDOW_LAYOUT" -w "$WINDOW_NAME" "$@"
+	tmuxp_session_creator edit -n "$SESSION_NAME" -i "$NWIN" --add-window -l "$WINDOW_LAYOUT" -w "$WINDOW_NAME" "$@"
 }
 
+alias capturesession="test \$TMUX && tmuxp_session_creator capture"
 alias saveall="test \$TMUX && __saveall"
 alias savewin="test \$TMUX && __savewin"
 alias setfocus="test \$TMUX && __setfocus"
 
 alias quitses='tmux kill-session; exit 0'
-alias listses='ls ~/.tmuxp/ | awk -F "/" "{print $NF}" | cut -d "." -f 1'
+alias listses='tmuxp_session_creator list -q'
 alias tmuxload="__tmuxload"
 
 function __tmuxload {
@@ -1,17 +1,48 @@
 use structopt::StructOpt;
 
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use crate::errors::Errcode;
+use crate::session::TmuxSession;
 
 pub mod create;
 pub mod edit;
+pub mod show;
+pub mod capture;
+pub mod dedupe;
+pub mod convert;
+pub mod import;
+pub mod export;
+pub mod list;
+pub mod rename;
+pub mod copy;
+pub mod delete;
+pub mod window;
+pub mod pane;
 
 use create::TmuxpSessionCreation;
 use edit::TmuxpSessionEdition;
+use show::TmuxpSessionShow;
+use capture::TmuxpSessionCapture;
+use dedupe::TmuxpSessionDedupe;
+use convert::TmuxpSessionConvert;
+use import::TmuxpSessionImport;
+use export::TmuxpSessionExport;
+use list::TmuxpSessionList;
+use rename::TmuxpSessionRename;
+use copy::TmuxpSessionCopy;
+use delete::TmuxpSessionDelete;
+use window::TmuxpSessionWindow;
+use pane::TmuxpSessionPane;
 
 macro_rules! cli_commands {
     ($($name:ident => $impl:ident),+) => {
+        // Only built once from the commandline arguments, boxing the variants is not worth it
+        #[allow(clippy::large_enum_variant)]
         #[derive(Debug, StructOpt)]
-        #[structopt(name = "tmuxph", about = "Manages tmuxp JSON files")]
+        #[structopt(name = "tmuxph", about = "Manages tmuxp JSON and YAML files")]
         pub enum Commands {
             $(
                 $name($impl),
@@ -37,10 +68,84 @@ pub trait CliSubCommand {
 
 cli_commands!(
     Create => TmuxpSessionCreation,
-    Edit => TmuxpSessionEdition
+    Edit => TmuxpSessionEdition,
+    Show => TmuxpSessionShow,
+    Capture => TmuxpSessionCapture,
+    Dedupe => TmuxpSessionDedupe,
+    Convert => TmuxpSessionConvert,
+    Import => TmuxpSessionImport,
+    Export => TmuxpSessionExport,
+    List => TmuxpSessionList,
+    Rename => TmuxpSessionRename,
+    Copy => TmuxpSessionCopy,
+    Delete => TmuxpSessionDelete,
+    Window => TmuxpSessionWindow,
+    Pane => TmuxpSessionPane
 );
 
 pub fn subcmd<T: CliSubCommand>(args: &T) -> Result<(), Errcode> {
     args.validate_args()?;
     args.execute_command()
 }
+
+/// Writes the profile, its files in other formats are removed
+pub fn write_profile(tmuxses: &TmuxSession) -> Result<(), Errcode> {
+    let removed = tmuxses.write_to_file()?;
+    report_removed(tmuxses, &removed)
+}
+
+pub fn report_removed(tmuxses: &TmuxSession, removed: &[PathBuf]) -> Result<(), Errcode> {
+    let fname = TmuxSession::get_session_fname(tmuxses.name(), tmuxses.format)?;
+    for r in removed.iter() {
+        println!("Removed {}, the profile is now in {}", r.display(), fname.display());
+    }
+    Ok(())
+}
+
+/// Argument with a format, parsed by the commandline parser but only checked by `validate_args`,
+/// so that an invalid value exits with the code of its error instead of the one of the parser
+#[derive(Debug)]
+pub struct CheckedArg<T>(Result<T, Errcode>);
+
+impl<T: FromStr<Err = Errcode>> FromStr for CheckedArg<T> {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<CheckedArg<T>, Infallible> {
+        Ok(CheckedArg(T::from_str(s)))
+    }
+}
+
+impl<T> CheckedArg<T> {
+    /// The value, the arguments are checked before the subcommand is executed
+    pub fn get(&self) -> &T {
+        self.0.as_ref().expect("argument not checked by validate_args")
+    }
+}
+
+/// Fails with the error of the first invalid argument
+pub fn check_args<'a, T: 'a>(args: impl IntoIterator<Item = &'a CheckedArg<T>>) -> Result<(), Errcode> {
+    args.into_iter().try_for_each(|a| a.0.as_ref().map(|_| ()).map_err(|e| e.clone()))
+}
+
+
+
+
+
+#[test]
+fn test_checked_args(){
+    use crate::layout::{LayoutTemplate, TermSize, TmuxLayout};
+    use crate::serialisation::FileFormat;
+
+    let size: CheckedArg<TermSize> = "80x24".parse().unwrap();
+    assert!(check_args(Some(&size)).is_ok());
+    assert_eq!(*size.get(), TermSize { width: 80, height: 24 });
+    let none: Option<CheckedArg<TermSize>> = None;
+    assert!(check_args(none.iter()).is_ok());
+
+    let invalid_code = |err: Result<(), Errcode>| err.unwrap_err().exit_code();
+    assert_eq!(invalid_code(check_args(Some(&"80".parse::<CheckedArg<TermSize>>().unwrap()))), 2);
+    assert_eq!(invalid_code(check_args(Some(&"xml".parse::<CheckedArg<FileFormat>>().unwrap()))), 2);
+    assert_eq!(invalid_code(check_args(Some(&"garbage".parse::<CheckedArg<TmuxLayout>>().unwrap()))), 5);
+    let templates: Vec<CheckedArg<LayoutTemplate>> = vec!["tiled".parse().unwrap(), "h(a,".parse().unwrap()];
+    assert_eq!(invalid_code(check_args(templates.iter())), 6);
+}
@@ -0,0 +1,68 @@
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::serialisation::FileFormat;
+use crate::procs::{CommandFilter, CommandRecovery, ProcFs};
+use crate::tmux::TmuxClient;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionCapture {
+    /// The running tmux session to capture, defaults to the current one
+    #[structopt(short="s", long)]
+    pub session: Option<String>,
+
+    /// The name of the Tmuxp profile to write, defaults to the name of the tmux session
+    #[structopt(short="n", long)]
+    pub name: Option<String>,
+
+    /// The tmux executable to query
+    #[structopt(long, default_value = "tmux")]
+    pub tmux: PathBuf,
+
+    /// Only save the commands of these programs (can be repeated)
+    #[structopt(long="allow")]
+    pub allow: Vec<String>,
+
+    /// Never save the commands of these programs, in addition to the shells (can be repeated)
+    #[structopt(long="deny")]
+    pub deny: Vec<String>,
+
+    /// Format of the written file (json / yaml), defaults to json
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="o", long="dump")]
+    pub dump: bool,
+}
+
+impl CliSubCommand for TmuxpSessionCapture {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let client = TmuxClient::new(self.tmux.clone());
+        let session = match &self.session {
+            Some(s) => s.clone(),
+            None => client.current_session()?,
+        };
+        let mut filter = CommandFilter { allow: self.allow.clone(), ..CommandFilter::default() };
+        filter.deny.extend(self.deny.iter().cloned());
+        let recovery = CommandRecovery { procfs: ProcFs::default(), filter };
+        let mut tmuxses = client.capture_session(&session, self.name.as_ref().unwrap_or(&session), &recovery)?;
+
+        if let Some(format) = &self.format {
+            tmuxses.format = *format.get();
+        }
+
+        if self.dump {
+            tmuxses.dump()?;
+        } else {
+            write_profile(&tmuxses)?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.format.iter())
+    }
+}
@@ -0,0 +1,72 @@
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::session::TmuxSession;
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::serialisation::FileFormat;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionConvert {
+    /// The name of the Tmuxp profile to convert, its file is replaced by the converted one
+    #[structopt(short="n", long, required_unless="input", conflicts_with="input")]
+    pub name: Option<String>,
+
+    /// A session file to convert instead of a profile, its format is found from its extension
+    #[structopt(short="I", long)]
+    pub input: Option<PathBuf>,
+
+    /// The file to write, its format is found from its extension
+    #[structopt(short="O", long)]
+    pub output: Option<PathBuf>,
+
+    /// The format to convert to (json / yaml), defaults to the format of the output file,
+    /// or to the other format
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
+    pub dump: bool,
+}
+
+impl CliSubCommand for TmuxpSessionConvert {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = match (&self.name, &self.input) {
+            (Some(name), _) => TmuxSession::load(name)?,
+            (None, Some(input)) => TmuxSession::load_file(input)?,
+            (None, None) => unreachable!(),
+        };
+
+        let from = tmuxses.format;
+        tmuxses.format = self.format.as_ref().map(|f| *f.get())
+            .or_else(|| self.output.as_ref().and_then(|o| FileFormat::from_path(o)))
+            .unwrap_or(match from {
+                FileFormat::Json => FileFormat::Yaml,
+                FileFormat::Yaml => FileFormat::Json,
+            });
+
+        if self.dump {
+            tmuxses.dump()?;
+        } else if let Some(output) = &self.output {
+            tmuxses.write_to(output)?;
+        } else if self.name.is_some() {
+            write_profile(&tmuxses)?;
+        } else {
+            let input = self.input.as_ref().unwrap();
+            tmuxses.write_to(&input.with_extension(tmuxses.format.extensions()[0]))?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.format.iter())?;
+        if let Some(output) = &self.output {
+            if self.format.is_none() && FileFormat::from_path(output).is_none() {
+                return Err(Errcode::ArgValidationError("output, unknown extension, use --format"));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use structopt::StructOpt;
+
+use crate::cli::{report_removed, CliSubCommand};
+use crate::errors::Errcode;
+use crate::session::TmuxSession;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionCopy {
+    /// The name of the Tmuxp profile to copy
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// The name of the new profile and of its session
+    #[structopt(short="t", long="to")]
+    pub new_name: String,
+
+    /// Replace the profile with the new name if it exists
+    #[structopt(short="f", long)]
+    pub force: bool,
+}
+
+impl CliSubCommand for TmuxpSessionCopy {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+        let removed = tmuxses.write_as(&self.new_name, self.force)?;
+        report_removed(&tmuxses, &removed)?;
+        let new_file = TmuxSession::get_session_fname(&self.new_name, tmuxses.format)?;
+        println!("Profile \"{}\" copied to \"{}\" ({})", self.name, self.new_name, new_file.display());
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        if self.name == self.new_name {
+            return Err(Errcode::ArgValidationError("new name, a profile cannot be copied onto itself"));
+        }
+        Ok(())
+    }
+}
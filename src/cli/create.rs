@@ -1,12 +1,36 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::convert::TryFrom;
 
 use structopt::StructOpt;
 
-use crate::cli::CliSubCommand;
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
 use crate::session::TmuxSession;
 use crate::errors::Errcode;
+use crate::serialisation::FileFormat;
 use crate::window::WindowDescription;
+use crate::layout::{LayoutTemplate, TermSize};
+
+/// Layout template to apply to a window of the created session
+#[derive(Debug)]
+pub struct WindowLayoutTemplate {
+    pub window: usize,
+    pub template: LayoutTemplate,
+}
+
+impl FromStr for WindowLayoutTemplate {
+    type Err = Errcode;
+
+    // Format:      WINDOW_INDEX=TEMPLATE
+    fn from_str(s: &str) -> Result<WindowLayoutTemplate, Errcode> {
+        let invalid = || Errcode::ArgValidationError("layout template, expected WINDOW_INDEX=TEMPLATE");
+        let (window, template) = s.split_once('=').ok_or_else(invalid)?;
+        Ok(WindowLayoutTemplate {
+            window: usize::from_str(window).map_err(|_| invalid())?,
+            template: LayoutTemplate::from_str(template)?,
+        })
+    }
+}
 
 #[derive(Debug, StructOpt)]
 pub struct TmuxpSessionCreation {
@@ -31,6 +55,22 @@ pub struct TmuxpSessionCreation {
     #[structopt(short, long, default_value = "")]
     pub windows_description: Vec<WindowDescription>,
 
+    /// Generate the layout of a window from a tmux preset or a split description
+    /// (see the edit subcommand), can be passed multiple times
+    ///
+    /// format:      WINDOW_INDEX=TEMPLATE
+    ///
+    /// Example:    0=main-vertical
+    #[structopt(short="L", long)]
+    pub layout_template: Vec<CheckedArg<WindowLayoutTemplate>>,
+
+    /// The terminal size (WIDTHxHEIGHT) used to generate layouts, defaults to the current terminal size
+    #[structopt(long)]
+    pub term_size: Option<CheckedArg<TermSize>>,
+
+    /// Format of the written file (json / yaml), defaults to json
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
     /// Create a default "bash" tmux session
     #[structopt(short="D", long)]
     pub default: bool,
@@ -42,23 +82,30 @@ pub struct TmuxpSessionCreation {
 
 impl CliSubCommand for TmuxpSessionCreation {
     fn execute_command(&self) -> Result<(), Errcode>{
-        let tmuxses = match TmuxSession::try_from(self){
+        let mut tmuxses = match TmuxSession::try_from(self){
             Ok(ses) => ses,
             Err(e) => {
-                println!("Error while creating TmuxSession from commandline arguments");
+                eprintln!("Error while creating TmuxSession from commandline arguments");
                 return Err(e);
             }
         };
         
+        if let Some(format) = &self.format {
+            tmuxses.format = *format.get();
+        }
+
         if self.dump {
             tmuxses.dump()?;
         } else {
-            tmuxses.write_to_file()?;
+            write_profile(&tmuxses)?;
         }
         Ok(())
     }
 
     fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.layout_template.iter())?;
+        check_args(self.term_size.iter())?;
+        check_args(self.format.iter())?;
         if !self.start_directory.is_dir(){
             return Err(Errcode::ArgValidationError("start directory"))
         }
@@ -0,0 +1,56 @@
+use structopt::StructOpt;
+
+use crate::session::TmuxSession;
+use crate::cli::{write_profile, CliSubCommand};
+use crate::errors::Errcode;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionDedupe {
+    /// The name of the Tmuxp profile to dedupe
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// Only dedupe this window, the session is left untouched
+    #[structopt(short="i", long)]
+    pub window_ind: Option<usize>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
+    pub dump: bool,
+}
+
+impl CliSubCommand for TmuxpSessionDedupe {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+
+        let indexes: Vec<usize> = match self.window_ind {
+            Some(i) => vec![i],
+            None => (0..tmuxses.windows.len()).collect(),
+        };
+        for ind in indexes {
+            let hoisted = tmuxses.get_window_ref(ind)?.dedupe();
+            if !hoisted.is_empty() {
+                println!("Window {}: moved {:?} to the commands run before the panes", ind, hoisted);
+            }
+        }
+
+        // Only when all the windows are deduped, the other ones could share another prefix
+        if self.window_ind.is_none() {
+            let hoisted = tmuxses.dedupe_windows();
+            if !hoisted.is_empty() {
+                println!("Session: moved {:?} to the commands run before the panes", hoisted);
+            }
+        }
+
+        if self.dump {
+            tmuxses.dump()?;
+        } else {
+            write_profile(&tmuxses)?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        Ok(())
+    }
+}
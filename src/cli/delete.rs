@@ -0,0 +1,40 @@
+use structopt::StructOpt;
+
+use std::io;
+
+use crate::cli::CliSubCommand;
+use crate::errors::Errcode;
+use crate::prompt::confirm;
+use crate::session::TmuxSession;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionDelete {
+    /// The name of the Tmuxp profile to delete
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// Don't ask for a confirmation
+    #[structopt(short="y", long)]
+    pub yes: bool,
+}
+
+impl CliSubCommand for TmuxpSessionDelete {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let fname = TmuxSession::profile_file(&self.name)?;
+        if !self.yes {
+            let question = format!("Delete the profile \"{}\" ({})?", self.name, fname.display());
+            let stdin = io::stdin();
+            if !confirm(&question, &mut stdin.lock(), &mut io::stdout())? {
+                return Err(Errcode::UserAborted);
+            }
+        }
+        for fname in TmuxSession::delete_profile(&self.name)? {
+            println!("Removed {}", fname.display());
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        Ok(())
+    }
+}
@@ -1,10 +1,17 @@
 use structopt::StructOpt;
 
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use crate::session::TmuxSession;
-use crate::cli::CliSubCommand;
+use crate::layout::{LayoutTemplate, SplitDirection, TermSize, TmuxLayout};
+use crate::pane::TmuxPane;
+use crate::window::{PaneCommand, PaneSplit, PaneSwap, TmuxWindow, DEFAULT_PANE_CMD};
+use crate::reconcile::{ExtraPanesPolicy, PaneMapping, Reconciliation};
+use crate::options::OptionAssignment;
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
 use crate::errors::Errcode;
+use crate::serialisation::FileFormat;
 
 #[derive(Debug, StructOpt)]
 pub struct TmuxpSessionEdition {
@@ -12,22 +19,96 @@ pub struct TmuxpSessionEdition {
     #[structopt(short="n", long)]
     pub name: String,
 
-    /// The window to modify
+    /// The window to modify, only needed by the options modifying a window
     #[structopt(short="i", long)]
-    pub window_ind: usize,
+    pub window_ind: Option<usize>,
+
+    /// Add the window at the end of the session if it doesn't exist yet,
+    /// -i has to be the number of windows of the session
+    #[structopt(long)]
+    pub add_window: bool,
 
     /// The layout to apply to the window
     #[structopt(short="l", long,)]
-    pub layout: Option<String>,
+    pub layout: Option<CheckedArg<TmuxLayout>>,
+
+    /// Generate the layout of the window from a tmux preset (even-horizontal, even-vertical,
+    /// main-horizontal, main-vertical, tiled) or from a split description
+    ///
+    /// format:      NAME  |  h(<SIZE:>CHILD,...)  |  v(<SIZE:>CHILD,...)
+    ///
+    /// Example:    h(60%:v(a,b),c)
+    #[structopt(short="L", long, conflicts_with="layout")]
+    pub layout_template: Option<CheckedArg<LayoutTemplate>>,
+
+    /// The terminal size (WIDTHxHEIGHT) used to generate layouts, defaults to the current terminal size
+    #[structopt(long)]
+    pub term_size: Option<CheckedArg<TermSize>>,
+
+    /// Rescale the layout of the window to a new terminal size (WIDTHxHEIGHT)
+    #[structopt(long)]
+    pub resize_layout: Option<CheckedArg<TermSize>>,
+
+    /// Rescale the layouts of all the windows of the session to a new terminal size (WIDTHxHEIGHT)
+    #[structopt(long)]
+    pub resize_all_layouts: Option<CheckedArg<TermSize>>,
+
+    /// Split a pane in two, the new pane is placed after it and uses PERCENT of its space (50% by default)
+    ///
+    /// format:      PANE:h|v<:PERCENT%>        (h: side by side, v: stacked)
+    #[structopt(long)]
+    pub split_pane: Option<CheckedArg<PaneSplit>>,
+
+
+    /// What to do when a new layout has less panes than the window has commands:
+    /// prompt, drop-last, drop-first, keep-blank (only drop the panes without command), fail
+    #[structopt(long, default_value = "prompt")]
+    pub on_extra_panes: CheckedArg<ExtraPanesPolicy>,
+
+    /// The command of the panes created by a new layout (blank by default)
+    /// or by --split-pane ("clear && bash" by default)
+    #[structopt(long)]
+    pub new_pane_command: Option<String>,
+
+    /// Fail instead of asking anything
+    #[structopt(long)]
+    pub non_interactive: bool,
+
+    /// JSON file giving for each pane of the new layout, the index of the pane whose
+    /// command it keeps, a command, or null for a new pane
+    ///
+    /// Example:    [0, 2, "htop", null]
+    #[structopt(long)]
+    pub pane_mapping: Option<PathBuf>,
+
+    /// Close a pane, its space is given to its neighbour
+    #[structopt(long)]
+    pub close_pane: Option<usize>,
+
+    /// Swap the commands of two panes, format:      PANE:PANE
+    #[structopt(long)]
+    pub swap_panes: Option<CheckedArg<PaneSwap>>,
+
+    /// Turn the split containing the pane by 90 degrees
+    #[structopt(long)]
+    pub rotate_split: Option<usize>,
 
     /// The window name to use
     #[structopt(short="w", long,)]
     pub window_name: Option<String>,
 
-    //TODO FIXME    Keep track of the order in which commands are passed
-    /// The commands to pass to each pane. Can be passed multiple times
+    /// The commands to pass to each pane, in the order of the panes. Can be passed multiple times
+    ///
+    /// PANE=CMD only changes the command of this pane, once the layout changes are applied
+    ///
+    /// Example:    -c nvim -c htop     |     -c 2="cargo test"
     #[structopt(short="c", long="command")]
-    pub commandlist: Vec<String>,
+    pub commandlist: Vec<CheckedArg<PaneCommand>>,
+
+    /// Add a pane running this command at the end of the window, the last pane is split
+    /// to make room for it (can be repeated)
+    #[structopt(long="append-command")]
+    pub append_commands: Vec<String>,
     
     /// The pane / command to focus on the window
     #[structopt(short="f", long)]
@@ -37,58 +118,311 @@ pub struct TmuxpSessionEdition {
     #[structopt(short="F", long)]
     pub window_focused: bool,
 
+    /// Set a tmux option of the window (can be repeated), format:      NAME=VALUE
+    ///
+    /// Example:    --option main-pane-width=120 --option synchronize-panes=on
+    #[structopt(long="option")]
+    pub options: Vec<CheckedArg<OptionAssignment>>,
+
+    /// Remove an option of the window (can be repeated)
+    #[structopt(long="unset-option")]
+    pub unset_options: Vec<String>,
+
+    /// Set a tmux option of the window once its panes are created (can be repeated), format:      NAME=VALUE
+    #[structopt(long="option-after")]
+    pub options_after: Vec<CheckedArg<OptionAssignment>>,
+
+    /// Remove an option set once the panes are created (can be repeated)
+    #[structopt(long="unset-option-after")]
+    pub unset_options_after: Vec<String>,
+
     /// Change the start directory of the window
     #[structopt(short="d", long)]
     pub start_directory: Option<PathBuf>,
 
-    /// The layout to apply to the window
-    #[structopt(short="D", long,)]
+    /// Commands run in every pane of the window before its own ones, replace the current ones (can be repeated)
+    #[structopt(long="command-before")]
+    pub command_before: Vec<String>,
+
+    /// Remove the commands run in every pane of the window before its own ones
+    #[structopt(long, conflicts_with="command-before")]
+    pub clear_commands_before: bool,
+
+    /// Set a tmux option of the session (can be repeated), format:      NAME=VALUE
+    #[structopt(long="session-option")]
+    pub session_options: Vec<CheckedArg<OptionAssignment>>,
+
+    /// Remove an option of the session (can be repeated)
+    #[structopt(long="unset-session-option")]
+    pub unset_session_options: Vec<String>,
+
+    /// Set a global tmux option (can be repeated), format:      NAME=VALUE
+    #[structopt(long="global-option")]
+    pub global_options: Vec<CheckedArg<OptionAssignment>>,
+
+    /// Remove a global option (can be repeated)
+    #[structopt(long="unset-global-option")]
+    pub unset_global_options: Vec<String>,
+
+    /// Set an environment variable of the session (can be repeated), format:      NAME=VALUE
+    #[structopt(long="env")]
+    pub environment: Vec<CheckedArg<OptionAssignment>>,
+
+    /// Remove an environment variable of the session (can be repeated)
+    #[structopt(long="unset-env")]
+    pub unset_environment: Vec<String>,
+
+    /// Commands run in every pane of the session before its own ones, replace the current ones (can be repeated)
+    #[structopt(long="session-command-before")]
+    pub session_command_before: Vec<String>,
+
+    /// Remove the commands run in every pane of the session before its own ones
+    #[structopt(long, conflicts_with="session-command-before")]
+    pub clear_session_commands_before: bool,
+
+    /// Script run before creating the session, an empty one removes it
+    #[structopt(long)]
+    pub before_script: Option<String>,
+
+    /// Keep the commands sent to the panes out of the shell history (on / off)
+    #[structopt(long)]
+    pub suppress_history: Option<CheckedArg<OnOff>>,
+
+    /// tmux socket to start the session on, an empty one removes it
+    #[structopt(long)]
+    pub socket_name: Option<String>,
+
+    /// Format to write the file in (json / yaml), its file in the other format is removed,
+    /// defaults to the format it was loaded from
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
     pub dump: bool,
 }
 
-impl CliSubCommand for TmuxpSessionEdition {
-    fn execute_command(&self) -> Result<(), Errcode>{
-        let mut tmuxses = TmuxSession::load(&self.name)?;
+/// Boolean given on the commandline as on / off
+#[derive(Debug, Clone, Copy)]
+pub struct OnOff(pub bool);
 
-        let mut win = match tmuxses.get_window_ref(self.window_ind){
-            Ok(w) => w,
-            Err(_) => tmuxses.init_new_window()?,
-        };
+impl FromStr for OnOff {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<OnOff, Errcode> {
+        match s {
+            "on" | "true" | "yes" => Ok(OnOff(true)),
+            "off" | "false" | "no" => Ok(OnOff(false)),
+            _ => Err(Errcode::ArgValidationError("expected on or off")),
+        }
+    }
+}
+
+// Empty strings remove the setting
+fn optional_setting(val: &str) -> Option<String> {
+    if val.is_empty() { None } else { Some(val.to_string()) }
+}
+
+impl TmuxpSessionEdition {
+    fn edits_window(&self) -> bool {
+        self.layout.is_some() || self.layout_template.is_some() || self.resize_layout.is_some()
+            || self.split_pane.is_some() || self.close_pane.is_some() || self.swap_panes.is_some()
+            || self.rotate_split.is_some() || self.pane_mapping.is_some() || self.window_name.is_some()
+            || !self.commandlist.is_empty() || !self.append_commands.is_empty() || self.focus.is_some() || self.window_focused
+            || self.start_directory.is_some() || !self.options.is_empty() || !self.unset_options.is_empty()
+            || !self.options_after.is_empty() || !self.unset_options_after.is_empty()
+            || !self.command_before.is_empty() || self.clear_commands_before || self.add_window
+    }
 
-        let cmdslen = self.commandlist.len();
-        if cmdslen > 0 {
-            win.panes.set_panes_cmds(&self.commandlist);
+    // Checked before the profile is loaded, nothing is asked when an argument is invalid
+    fn check_formats(&self) -> Result<(), Errcode> {
+        // The layout string given is reported as an invalid layout, like the templates
+        check_args(self.layout.iter()).map_err(|e| Errcode::LayoutError(e.details()))?;
+        check_args(self.layout_template.iter())?;
+        check_args(self.term_size.iter().chain(self.resize_layout.iter()).chain(self.resize_all_layouts.iter()))?;
+        check_args(self.split_pane.iter())?;
+        check_args(Some(&self.on_extra_panes))?;
+        check_args(self.swap_panes.iter())?;
+        check_args(self.commandlist.iter())?;
+        check_args(self.options.iter().chain(self.options_after.iter()).chain(self.session_options.iter())
+            .chain(self.global_options.iter()).chain(self.environment.iter()))?;
+        check_args(self.suppress_history.iter())?;
+        check_args(self.format.iter())
+    }
+
+    fn edit_window(&self, win: &mut TmuxWindow) -> Result<(), Errcode> {
+        let cmds: Vec<String> = self.commandlist.iter().filter_map(|c| match c.get() {
+            PaneCommand::Positional(cmd) => Some(cmd.clone()),
+            PaneCommand::Indexed(_, _) => None,
+        }).collect();
+        if !cmds.is_empty() {
+            win.panes.set_panes_cmds(&cmds);
         }
 
         if let Some(p) = &self.start_directory {
-            win.start_directory = p.canonicalize()?.clone();
+            win.start_directory = Some(p.canonicalize()?);
         }
 
         if let Some(f) = &self.focus {
             win.panes.set_focus(*f)?;
         }
 
+        let rec = Reconciliation {
+            policy: *self.on_extra_panes.get(),
+            new_pane_command: self.new_pane_command.clone(),
+            interactive: !self.non_interactive,
+            mapping: self.pane_mapping.as_ref().map(|p| PaneMapping::load(p)).transpose()?,
+        };
+
         if let Some(l) = &self.layout {
-            win.set_layout(l)?;
+            win.set_layout(l.get(), &rec)?;
+        }
+
+        let size = self.term_size.as_ref().map(|s| *s.get()).unwrap_or_else(TermSize::current);
+        if let Some(t) = self.layout_template.as_ref().map(CheckedArg::get) {
+            win.set_layout(&t.generate(&size, win.panes.nb_panes())?, &rec)?;
+        }
+
+        if let Some(s) = &self.split_pane {
+            let cmd = self.new_pane_command.as_deref().unwrap_or(DEFAULT_PANE_CMD);
+            win.split_pane(s.get(), cmd.to_string(), &size)?;
+        }
+
+        if let Some(p) = self.close_pane {
+            win.close_pane(p, &size)?;
+        }
+
+        if let Some(PaneSwap(a, b)) = self.swap_panes.as_ref().map(CheckedArg::get) {
+            win.swap_panes(*a, *b)?;
+        }
+
+        if let Some(p) = self.rotate_split {
+            win.rotate_split(p, &size)?;
+        }
+
+        if let Some(s) = &self.resize_layout {
+            win.resize_layout(s.get())?;
+        }
+
+        for cmd in self.append_commands.iter() {
+            let at = win.panes.nb_panes();
+            win.add_pane(at, TmuxPane::from_cmd(cmd.clone()), SplitDirection::Vertical, 50)?;
+        }
+        for c in self.commandlist.iter() {
+            if let PaneCommand::Indexed(pane, cmd) = c.get() {
+                win.panes.set_pane_cmd(*pane, cmd.clone())?;
+            }
         }
 
         if let Some(n) = &self.window_name {
             win.window_name = n.clone();
         }
 
-        if self.window_focused {
-            tmuxses.set_window_focus(self.window_ind)?;
+        for o in self.unset_options.iter() {
+            win.options.unset(o)?;
+        }
+        for o in self.options.iter().map(CheckedArg::get) {
+            win.options.set(&o.name, &o.value)?;
+        }
+        for o in self.unset_options_after.iter() {
+            win.options_after.unset(o)?;
+        }
+        for o in self.options_after.iter().map(CheckedArg::get) {
+            win.options_after.set(&o.name, &o.value)?;
+        }
+
+        if !self.command_before.is_empty() {
+            win.shell_command_before = self.command_before.clone();
+        }
+        if self.clear_commands_before {
+            win.shell_command_before.clear();
+        }
+        Ok(())
+    }
+
+    fn edit_session(&self, tmuxses: &mut TmuxSession) -> Result<(), Errcode> {
+        for o in self.unset_session_options.iter() {
+            tmuxses.options.unset(o)?;
+        }
+        for o in self.session_options.iter().map(CheckedArg::get) {
+            tmuxses.options.set(&o.name, &o.value)?;
+        }
+        for o in self.unset_global_options.iter() {
+            tmuxses.global_options.unset(o)?;
+        }
+        for o in self.global_options.iter().map(CheckedArg::get) {
+            tmuxses.global_options.set(&o.name, &o.value)?;
+        }
+        for v in self.unset_environment.iter() {
+            tmuxses.unset_environment(v)?;
+        }
+        for v in self.environment.iter().map(CheckedArg::get) {
+            tmuxses.set_environment(&v.name, &v.value);
+        }
+
+        if !self.session_command_before.is_empty() {
+            tmuxses.shell_command_before = self.session_command_before.clone();
+        }
+        if self.clear_session_commands_before {
+            tmuxses.shell_command_before.clear();
+        }
+        if let Some(s) = &self.before_script {
+            tmuxses.before_script = optional_setting(s);
+        }
+        if let Some(OnOff(s)) = self.suppress_history.as_ref().map(CheckedArg::get) {
+            tmuxses.suppress_history = Some(*s);
+        }
+        if let Some(s) = &self.socket_name {
+            tmuxses.socket_name = optional_setting(s);
+        }
+
+        if let Some(s) = &self.resize_all_layouts {
+            tmuxses.resize_layouts(s.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl CliSubCommand for TmuxpSessionEdition {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+
+        if let Some(window_ind) = self.window_ind {
+            if self.add_window && window_ind == tmuxses.windows.len() {
+                let win = TmuxWindow::default(tmuxses.start_directory().to_path_buf());
+                tmuxses.add_window(None, win)?;
+            }
+            let win = tmuxses.get_window_ref(window_ind)?;
+            self.edit_window(win)?;
+
+            if self.window_focused {
+                tmuxses.set_window_focus(window_ind)?;
+            }
+        }
+
+        self.edit_session(&mut tmuxses)?;
+
+        if let Some(format) = &self.format {
+            tmuxses.format = *format.get();
         }
 
         if self.dump {
             tmuxses.dump()?;
         } else {
-            tmuxses.write_to_file()?;
+            write_profile(&tmuxses)?;
         }
         Ok(())
     }
 
     fn validate_args(&self) -> Result<(), Errcode>{
+        if self.window_ind.is_none() && self.edits_window() {
+            return Err(Errcode::ArgValidationError("the window to modify is required (-i / --window-ind)"));
+        }
+        self.check_formats()?;
+        let indexed = self.commandlist.iter().filter(|c| matches!(c.get(), PaneCommand::Indexed(_, _))).count();
+        if indexed > 0 && indexed < self.commandlist.len() {
+            return Err(Errcode::ArgValidationError("commands, either PANE=CMD assignments or the commands of all the panes"));
+        }
         Ok(())
     }
 }
@@ -0,0 +1,83 @@
+use structopt::StructOpt;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::cli::{check_args, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::interop::tmux_script::TmuxScript;
+use crate::interop::zellij::ZellijConversion;
+use crate::layout::TermSize;
+use crate::session::TmuxSession;
+
+/// What the session is exported to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportTarget {
+    TmuxScript,
+    Zellij,
+}
+
+impl FromStr for ExportTarget {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<ExportTarget, Errcode> {
+        match s {
+            "tmux-script" => Ok(ExportTarget::TmuxScript),
+            "zellij" => Ok(ExportTarget::Zellij),
+            _ => Err(Errcode::ArgValidationError("export target, expected tmux-script or zellij")),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionExport {
+    /// What to export the session to (tmux-script, zellij)
+    #[structopt(long="to")]
+    pub target: CheckedArg<ExportTarget>,
+
+    /// The name of the Tmuxp profile to export
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// The file to write, the result is printed to stdout if not set
+    #[structopt(short="o", long)]
+    pub output: Option<PathBuf>,
+
+    /// The terminal size (WIDTHxHEIGHT) used to compute the sizes of the Zellij panes,
+    /// defaults to the current terminal size
+    #[structopt(long)]
+    pub term_size: Option<CheckedArg<TermSize>>,
+}
+
+impl CliSubCommand for TmuxpSessionExport {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let target = *self.target.get();
+        let tmuxses = TmuxSession::load(&self.name)?;
+        let content = match target {
+            ExportTarget::TmuxScript => TmuxScript::new(&tmuxses).generate(),
+            // The settings that cannot be exported are listed at the top of the layout
+            ExportTarget::Zellij => {
+                let size = self.term_size.as_ref().map(|s| *s.get()).unwrap_or_else(TermSize::current);
+                ZellijConversion::new(size).export(&tmuxses)
+            },
+        };
+
+        match &self.output {
+            Some(output) => {
+                fs::write(output, content).map_err(|e| Errcode::from(e).in_file(output))?;
+                if target == ExportTarget::TmuxScript {
+                    fs::set_permissions(output, fs::Permissions::from_mode(0o755))?;
+                }
+            },
+            None => print!("{}", content),
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(Some(&self.target))?;
+        check_args(self.term_size.iter())
+    }
+}
@@ -0,0 +1,173 @@
+use structopt::StructOpt;
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::interop::expand_home;
+use crate::interop::resurrect::{find_save_file, ResurrectImport};
+use crate::interop::tmuxinator::{find_project, TmuxinatorImport};
+use crate::interop::zellij::ZellijConversion;
+use crate::layout::TermSize;
+use crate::serialisation::FileFormat;
+use crate::session::TmuxSession;
+
+/// The tool the imported file comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportSource {
+    Tmuxinator,
+    Zellij,
+    Resurrect,
+}
+
+impl FromStr for ImportSource {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<ImportSource, Errcode> {
+        match s {
+            "tmuxinator" => Ok(ImportSource::Tmuxinator),
+            "zellij" => Ok(ImportSource::Zellij),
+            "resurrect" => Ok(ImportSource::Resurrect),
+            _ => Err(Errcode::ArgValidationError("import source, expected tmuxinator, zellij or resurrect")),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionImport {
+    /// The tool the file comes from (tmuxinator, zellij, resurrect)
+    #[structopt(long="from")]
+    pub source: CheckedArg<ImportSource>,
+
+    /// The file to import, or the name of a tmuxinator project
+    /// (defaults to the last save for tmux-resurrect)
+    #[structopt(short="p", long)]
+    pub path: Option<String>,
+
+    /// Only import this session of a tmux-resurrect save, all of them are imported if not set
+    #[structopt(short="s", long)]
+    pub session: Option<String>,
+
+    /// The name of the Tmuxp profile to write, defaults to the name of the imported session
+    /// (the name of the file for Zellij layouts)
+    #[structopt(short="n", long)]
+    pub name: Option<String>,
+
+    /// The terminal size (WIDTHxHEIGHT) the sizes of the Zellij panes are turned into a layout for,
+    /// defaults to the current terminal size
+    #[structopt(long)]
+    pub term_size: Option<CheckedArg<TermSize>>,
+
+    /// Format of the written file (json / yaml), defaults to json
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
+    pub dump: bool,
+
+    /// Replace the existing profiles with the same names
+    #[structopt(short="f", long)]
+    pub force: bool,
+}
+
+impl TmuxpSessionImport {
+    // Checked by validate_args for the sources needing it
+    fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or_default()
+    }
+
+    fn import_tmuxinator(&self) -> Result<TmuxSession, Errcode> {
+        let fname = find_project(self.path())?;
+        let mut import = TmuxinatorImport::default();
+        let content = fs::read_to_string(&fname).map_err(|e| Errcode::from(e).in_file(&fname))?;
+        let tmuxses = import.import(&content).map_err(|e| e.in_file(&fname))?;
+        for key in import.unmapped.iter() {
+            eprintln!("Warning: {}: \"{}\" has no tmuxp equivalent, not imported", fname.display(), key);
+        }
+        Ok(tmuxses)
+    }
+
+    fn import_zellij(&self) -> Result<TmuxSession, Errcode> {
+        let fname = Path::new(self.path());
+        let name = fname.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let mut conversion = ZellijConversion::new(self.term_size.as_ref().map(|s| *s.get()).unwrap_or_else(TermSize::current));
+        let content = fs::read_to_string(fname).map_err(|e| Errcode::from(e).in_file(fname))?;
+        let tmuxses = conversion.import(&content, &name).map_err(|e| e.in_file(fname))?;
+        for key in conversion.unmapped.iter() {
+            eprintln!("Warning: {}: \"{}\" has no tmuxp equivalent, not imported", fname.display(), key);
+        }
+        Ok(tmuxses)
+    }
+
+    fn import_resurrect(&self) -> Result<Vec<TmuxSession>, Errcode> {
+        let fname = match &self.path {
+            Some(path) => expand_home(path),
+            None => find_save_file()?,
+        };
+        let mut import = ResurrectImport::default();
+        let content = fs::read_to_string(&fname).map_err(|e| Errcode::from(e).in_file(&fname))?;
+        let mut sessions = import.import(&content).map_err(|e| e.in_file(&fname))?;
+        for kind in import.unmapped.iter() {
+            eprintln!("Warning: {}: \"{}\" records have no tmuxp equivalent, not imported", fname.display(), kind);
+        }
+
+        if let Some(session) = &self.session {
+            sessions.retain(|s| s.name() == session);
+            if sessions.is_empty() {
+                return Err(Errcode::ParsingError(format!("No session \"{}\" in {}", session, fname.display())));
+            }
+        }
+        Ok(sessions)
+    }
+}
+
+impl CliSubCommand for TmuxpSessionImport {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut sessions = match self.source.get() {
+            ImportSource::Tmuxinator => vec![self.import_tmuxinator()?],
+            ImportSource::Zellij => vec![self.import_zellij()?],
+            ImportSource::Resurrect => self.import_resurrect()?,
+        };
+        if self.name.is_some() && sessions.len() > 1 {
+            return Err(Errcode::ArgValidationError("name, the save has several sessions, choose one with --session"));
+        }
+
+        for tmuxses in sessions.iter_mut() {
+            if let Some(name) = &self.name {
+                tmuxses.rename(name);
+            }
+            if let Some(format) = &self.format {
+                tmuxses.format = *format.get();
+            }
+        }
+        if self.dump {
+            return sessions.iter().try_for_each(|s| s.dump());
+        }
+
+        // A save with several sessions isn't imported halfway
+        for tmuxses in sessions.iter() {
+            TmuxSession::check_overwrite(tmuxses.name(), self.force)?;
+        }
+        for tmuxses in sessions.iter() {
+            write_profile(tmuxses)?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(Some(&self.source))?;
+        check_args(self.term_size.iter())?;
+        check_args(self.format.iter())?;
+        let source = *self.source.get();
+        if self.path.is_none() && source != ImportSource::Resurrect {
+            return Err(Errcode::ArgValidationError("path, required for tmuxinator and zellij"));
+        }
+        if self.session.is_some() && source != ImportSource::Resurrect {
+            return Err(Errcode::ArgValidationError("session, only for tmux-resurrect saves"));
+        }
+        Ok(())
+    }
+}
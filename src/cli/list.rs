@@ -0,0 +1,80 @@
+use structopt::StructOpt;
+
+use std::fs;
+use std::time::SystemTime;
+
+use crate::cli::CliSubCommand;
+use crate::errors::Errcode;
+use crate::session::TmuxSession;
+
+const HEADER: [&str; 5] = ["NAME", "WINDOWS", "PANES", "MODIFIED", "START DIRECTORY"];
+
+// From the largest unit
+const AGE_UNITS: [(&str, u64); 4] = [("week", 7 * 86400), ("day", 86400), ("hour", 3600), ("minute", 60)];
+
+fn age(modified: SystemTime) -> String {
+    let secs = SystemTime::now().duration_since(modified).map(|d| d.as_secs()).unwrap_or(0);
+    for (unit, len) in AGE_UNITS.iter() {
+        if secs >= *len {
+            let n = secs / len;
+            return format!("{} {}{} ago", n, unit, if n > 1 { "s" } else { "" });
+        }
+    }
+    "just now".to_string()
+}
+
+// Format:      NAME  WINDOWS  PANES  MODIFIED  START_DIRECTORY
+fn profile_row(name: &str) -> Result<[String; 5], Errcode> {
+    let modified = fs::metadata(TmuxSession::profile_file(name)?)?.modified()?;
+    let tmuxses = TmuxSession::load(name)?;
+    let npanes: usize = tmuxses.windows.iter().map(|w| w.panes.nb_panes()).sum();
+    Ok([
+        name.to_string(),
+        tmuxses.windows.len().to_string(),
+        npanes.to_string(),
+        age(modified),
+        tmuxses.start_directory().display().to_string(),
+    ])
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionList {
+    /// Only print the names of the profiles, one per line
+    #[structopt(short="q", long)]
+    pub quiet: bool,
+}
+
+impl CliSubCommand for TmuxpSessionList {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let names = TmuxSession::list_profiles()?;
+        if self.quiet {
+            for name in names.iter() {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
+
+        let mut rows = vec![HEADER.iter().map(|h| h.to_string()).collect::<Vec<String>>()];
+        for name in names.iter() {
+            // A broken profile shouldn't hide the others
+            match profile_row(name) {
+                Ok(row) => rows.push(row.to_vec()),
+                Err(e) => rows.push(vec![name.clone(), "-".to_string(), "-".to_string(), "-".to_string(),
+                    format!("(unable to load: {})", e)]),
+            }
+        }
+
+        let widths: Vec<usize> = (0..HEADER.len())
+            .map(|n| rows.iter().map(|r| r[n].chars().count()).max().unwrap_or(0))
+            .collect();
+        for row in rows.iter() {
+            let line: Vec<String> = row.iter().zip(widths.iter()).map(|(c, w)| format!("{:<1$}", c, w)).collect();
+            println!("{}", line.join("  ").trim_end());
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::layout::SplitDirection;
+use crate::pane::TmuxPane;
+use crate::serialisation::FileFormat;
+use crate::session::TmuxSession;
+use crate::window::DEFAULT_PANE_CMD;
+
+#[derive(Debug, StructOpt)]
+pub enum PaneOperation {
+    /// Add a pane, at the end of the window by default. If the window has a layout,
+    /// the pane before it (the first one for position 0) is split to make room for it
+    Add {
+        /// The position of the new pane
+        #[structopt(long)]
+        at: Option<usize>,
+
+        /// The command of the new pane ("clear && bash" by default)
+        #[structopt(short="c", long)]
+        command: Option<String>,
+
+        /// The directory the pane starts in, the one of the window if not set
+        #[structopt(short="d", long)]
+        start_directory: Option<PathBuf>,
+
+        /// How the split pane and the new one are arranged (h: side by side, v: stacked)
+        #[structopt(long, default_value = "v")]
+        direction: CheckedArg<SplitDirection>,
+
+        /// The part of the split pane used by the new pane, in percent
+        #[structopt(long, default_value = "50")]
+        percent: usize,
+    },
+
+    /// Remove a pane, the focus goes to the next one if it was focused
+    Remove {
+        index: usize,
+    },
+
+    /// Move a pane to another position, the panes in between are shifted
+    Move {
+        from: usize,
+        to: usize,
+    },
+
+    /// Replace the command of a pane, it keeps its other settings
+    SetCmd {
+        index: usize,
+        command: String,
+    },
+
+    /// Focus a pane of the window
+    Focus {
+        index: usize,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionPane {
+    /// The name of the Tmuxp profile to edit
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// The window of the panes
+    #[structopt(short="i", long)]
+    pub window_ind: usize,
+
+    #[structopt(subcommand)]
+    pub operation: PaneOperation,
+
+    /// Format of the written file (json / yaml), defaults to the format of the profile
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
+    pub dump: bool,
+}
+
+impl CliSubCommand for TmuxpSessionPane {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+        let win = tmuxses.get_window_ref(self.window_ind)?;
+
+        match &self.operation {
+            PaneOperation::Add { at, command, start_directory, direction, percent } => {
+                let mut pane = TmuxPane::from_cmd(command.clone().unwrap_or_else(|| DEFAULT_PANE_CMD.to_string()));
+                pane.start_directory = start_directory.clone();
+                let at = at.unwrap_or_else(|| win.panes.nb_panes());
+                win.add_pane(at, pane, *direction.get(), *percent)?;
+            },
+            PaneOperation::Remove { index } => win.remove_pane(*index)?,
+            PaneOperation::Move { from, to } => win.move_pane(*from, *to)?,
+            PaneOperation::SetCmd { index, command } => win.panes.set_pane_cmd(*index, command.clone())?,
+            PaneOperation::Focus { index } => win.panes.set_focus(*index)?,
+        }
+
+        if let Some(format) = &self.format {
+            tmuxses.format = *format.get();
+        }
+
+        if self.dump {
+            tmuxses.dump()?;
+        } else {
+            write_profile(&tmuxses)?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.format.iter())?;
+        if let PaneOperation::Add { direction, percent, .. } = &self.operation {
+            check_args(Some(direction))?;
+            if *percent == 0 || *percent >= 100 {
+                return Err(Errcode::ArgValidationError("pane split percentage, expected between 1% and 99%"));
+            }
+        }
+        Ok(())
+    }
+}
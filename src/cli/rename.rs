@@ -0,0 +1,44 @@
+use structopt::StructOpt;
+
+use std::fs;
+
+use crate::cli::{report_removed, CliSubCommand};
+use crate::errors::Errcode;
+use crate::session::TmuxSession;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionRename {
+    /// The name of the Tmuxp profile to rename
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// The new name of the profile and of its session
+    #[structopt(short="t", long="to")]
+    pub new_name: String,
+
+    /// Replace the profile with the new name if it exists
+    #[structopt(short="f", long)]
+    pub force: bool,
+}
+
+impl CliSubCommand for TmuxpSessionRename {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let old_files = TmuxSession::find_session_files(&self.name)?;
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+        let removed = tmuxses.write_as(&self.new_name, self.force)?;
+        report_removed(&tmuxses, &removed)?;
+        let new_file = TmuxSession::get_session_fname(&self.new_name, tmuxses.format)?;
+        for fname in old_files.into_iter().filter(|f| *f != new_file) {
+            fs::remove_file(fname)?;
+        }
+        println!("Profile \"{}\" renamed to \"{}\" ({})", self.name, self.new_name, new_file.display());
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        if self.name == self.new_name {
+            return Err(Errcode::ArgValidationError("new name, the profile already has this name"));
+        }
+        Ok(())
+    }
+}
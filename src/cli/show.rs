@@ -0,0 +1,51 @@
+use structopt::StructOpt;
+
+use crate::session::TmuxSession;
+use crate::cli::{check_args, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::layout::TermSize;
+use crate::preview::render_window;
+
+// Lines used by the window title and the blank line around it
+const WINDOW_HEADER_LINES: usize = 2;
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionShow {
+    /// The name of the Tmuxp profile to show
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    /// Only show this window
+    #[structopt(short="i", long)]
+    pub window_ind: Option<usize>,
+
+    /// The size (WIDTHxHEIGHT) of the preview of a window, defaults to the current terminal size
+    #[structopt(long)]
+    pub term_size: Option<CheckedArg<TermSize>>,
+}
+
+impl CliSubCommand for TmuxpSessionShow {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let size = self.term_size.as_ref().map(|s| *s.get()).unwrap_or_else(|| {
+            let term = TermSize::current();
+            TermSize { width: term.width, height: term.height.saturating_sub(WINDOW_HEADER_LINES) }
+        });
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+
+        let indexes: Vec<usize> = match self.window_ind {
+            Some(i) => vec![i],
+            None => (0..tmuxses.windows.len()).collect(),
+        };
+
+        for ind in indexes {
+            let win = tmuxses.get_window_ref(ind)?;
+            println!("\nWindow {}: {}{}", ind, win.window_name, if win.focus { " (focused)" } else { "" });
+            println!("{}", render_window(win, &size)?);
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.term_size.iter())
+    }
+}
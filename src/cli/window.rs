@@ -0,0 +1,103 @@
+use structopt::StructOpt;
+
+use std::convert::TryFrom;
+
+use crate::cli::{check_args, write_profile, CheckedArg, CliSubCommand};
+use crate::errors::Errcode;
+use crate::serialisation::FileFormat;
+use crate::session::TmuxSession;
+use crate::window::{TmuxWindow, WindowDescription};
+
+#[derive(Debug, StructOpt)]
+pub enum WindowOperation {
+    /// Add a window, at the end of the session by default
+    Add {
+        /// The position of the new window
+        #[structopt(long)]
+        at: Option<usize>,
+
+        /// The description of the window (see the create subcommand), a default window if not set
+        ///
+        /// format:      NAME:STARTDIR:AUTORENAME:FOCUSED_PANE:PANE0:<PANE1>:<etc...>
+        #[structopt(short="w", long="window-description")]
+        description: Option<WindowDescription>,
+
+        /// Make it the focused window of the session
+        #[structopt(short="F", long)]
+        focus: bool,
+    },
+
+    /// Remove a window, the focus goes to the next one if it was focused
+    Remove {
+        index: usize,
+    },
+
+    /// Move a window to another position, the windows in between are shifted
+    Move {
+        from: usize,
+        to: usize,
+    },
+
+    /// Swap the positions of two windows
+    Swap {
+        a: usize,
+        b: usize,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TmuxpSessionWindow {
+    /// The name of the Tmuxp profile to edit
+    #[structopt(short="n", long)]
+    pub name: String,
+
+    #[structopt(subcommand)]
+    pub operation: WindowOperation,
+
+    /// Format of the written file (json / yaml), defaults to the format of the profile
+    #[structopt(long)]
+    pub format: Option<CheckedArg<FileFormat>>,
+
+    /// Dump the content to stdout instead of writing it to the file
+    #[structopt(short="D", long)]
+    pub dump: bool,
+}
+
+impl CliSubCommand for TmuxpSessionWindow {
+    fn execute_command(&self) -> Result<(), Errcode>{
+        let mut tmuxses = TmuxSession::load(&self.name)?;
+
+        match &self.operation {
+            WindowOperation::Add { at, description, focus } => {
+                let mut win = match description {
+                    Some(d) => TmuxWindow::try_from(d)?,
+                    None => TmuxWindow::default(tmuxses.start_directory().to_path_buf()),
+                };
+                win.focus = *focus;
+                let at = tmuxses.add_window(*at, win)?;
+                println!("Added window {}", at);
+            },
+            WindowOperation::Remove { index } => {
+                let win = tmuxses.remove_window(*index)?;
+                println!("Removed window {}: {}", index, win.window_name);
+            },
+            WindowOperation::Move { from, to } => tmuxses.move_window(*from, *to)?,
+            WindowOperation::Swap { a, b } => tmuxses.swap_windows(*a, *b)?,
+        }
+
+        if let Some(format) = &self.format {
+            tmuxses.format = *format.get();
+        }
+
+        if self.dump {
+            tmuxses.dump()?;
+        } else {
+            write_profile(&tmuxses)?;
+        }
+        Ok(())
+    }
+
+    fn validate_args(&self) -> Result<(), Errcode>{
+        check_args(self.format.iter())
+    }
+}
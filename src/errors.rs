@@ -1,42 +1,147 @@
 use std::fmt;
 use std::num::ParseIntError;
+use std::path::Path;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Errcode {
     ArgValidationError(&'static str),
     JsonError(String),
+    YamlError(String),
     FileError(String),
     ParsingError(String),
-    EnvError(u8),
+    LayoutError(String),
+    ReconciliationError(String),
+    UserAborted,
+    TmuxError(String),
+    HomeDirNotFound,
+    ProfileNotFound(String),
     OptionNotFound(String),
+    /// Index of the window, number of windows of the session
     WindowNotFound(usize, usize),
 }
 
-// TODO     Display custom error messages for variants
-
 pub fn handle_error(err: Errcode) -> i32 {
-    println!("Error occured: {:?}", err);
-    return 1;
+    eprintln!("Error: {}", err);
+    err.exit_code()
+}
+
+impl Errcode {
+    /// Exit code of the tool, by category of error (1 is used by the commandline parser):
+    ///
+    /// 2: invalid arguments, 3: profile not found, 4: window or option not found,
+    /// 5: invalid file content, 6: invalid layout, 7: file system error, 8: tmux error,
+    /// 9: commands not matching the panes, 10: aborted by the user
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Errcode::ArgValidationError(_) => 2,
+            Errcode::ProfileNotFound(_) => 3,
+            Errcode::WindowNotFound(_, _) | Errcode::OptionNotFound(_) => 4,
+            Errcode::JsonError(_) | Errcode::YamlError(_) | Errcode::ParsingError(_) => 5,
+            Errcode::LayoutError(_) => 6,
+            Errcode::FileError(_) | Errcode::HomeDirNotFound => 7,
+            Errcode::TmuxError(_) => 8,
+            Errcode::ReconciliationError(_) => 9,
+            Errcode::UserAborted => 10,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Errcode::ArgValidationError(_) => "Invalid argument",
+            Errcode::JsonError(_) => "Invalid JSON",
+            Errcode::YamlError(_) => "Invalid YAML",
+            Errcode::FileError(_) => "File error",
+            Errcode::ParsingError(_) => "Parsing error",
+            Errcode::LayoutError(_) => "Invalid layout",
+            Errcode::ReconciliationError(_) => "Cannot match the commands with the panes",
+            Errcode::UserAborted => "Aborted",
+            Errcode::TmuxError(_) => "tmux error",
+            Errcode::HomeDirNotFound => "Environment error",
+            Errcode::ProfileNotFound(_) => "Profile not found",
+            Errcode::OptionNotFound(_) => "Option not found",
+            Errcode::WindowNotFound(_, _) => "Window not found",
+        }
+    }
+
+    /// What went wrong, without the category of the error
+    pub fn details(&self) -> String {
+        match self {
+            Errcode::ArgValidationError(arg) => arg.to_string(),
+            Errcode::JsonError(msg) | Errcode::YamlError(msg) | Errcode::FileError(msg) | Errcode::ParsingError(msg)
+                | Errcode::LayoutError(msg) | Errcode::ReconciliationError(msg) | Errcode::TmuxError(msg) => msg.clone(),
+            Errcode::UserAborted => "cancelled from the prompt".to_string(),
+            Errcode::HomeDirNotFound => "unable to find the home directory, is $HOME set?".to_string(),
+            Errcode::ProfileNotFound(name) => format!("no profile \"{}\" in ~/.tmuxp/ (.json, .yaml or .yml)", name),
+            Errcode::OptionNotFound(name) => format!("the option \"{}\" is not set", name),
+            Errcode::WindowNotFound(index, nwin) => format!("no window {}, the session has {} window{} (numbered from 0)",
+                index, nwin, if *nwin > 1 { "s" } else { "" }),
+        }
+    }
+
+    /// Adds the file the error comes from to its message
+    pub fn in_file(self, path: &Path) -> Errcode {
+        let ctx = |msg: String| format!("{}: {}", path.display(), msg);
+        match self {
+            Errcode::JsonError(msg) => Errcode::JsonError(ctx(msg)),
+            Errcode::YamlError(msg) => Errcode::YamlError(ctx(msg)),
+            Errcode::FileError(msg) => Errcode::FileError(ctx(msg)),
+            Errcode::ParsingError(msg) => Errcode::ParsingError(ctx(msg)),
+            Errcode::LayoutError(msg) => Errcode::LayoutError(ctx(msg)),
+            e => e,
+        }
+    }
 }
 
 impl From<std::io::Error> for Errcode {
-    fn from(e: std::io::Error) -> Errcode { Errcode::FileError(format!("{:?}", e)) }
+    fn from(e: std::io::Error) -> Errcode { Errcode::FileError(e.to_string()) }
 }
 
 impl From<serde_json::Error> for Errcode {
-    fn from(e: serde_json::Error) -> Errcode { Errcode::JsonError(format!("{:?}", e)) }
+    fn from(e: serde_json::Error) -> Errcode { Errcode::JsonError(e.to_string()) }
+}
+
+impl From<serde_yaml::Error> for Errcode {
+    fn from(e: serde_yaml::Error) -> Errcode { Errcode::YamlError(e.to_string()) }
 }
 
 impl From<nom::Err<nom::error::Error<&str>>> for Errcode {
-    fn from(e: nom::Err<nom::error::Error<&str>>) -> Errcode { Errcode::ParsingError(format!("{:?}", e)) }
+    fn from(e: nom::Err<nom::error::Error<&str>>) -> Errcode {
+        Errcode::ParsingError(match e {
+            // Only the line where the parsing stopped
+            nom::Err::Error(e) | nom::Err::Failure(e) => format!("unexpected \"{}\" ({:?})",
+                e.input.lines().next().unwrap_or_default(), e.code),
+            nom::Err::Incomplete(_) => "incomplete input".to_string(),
+        })
+    }
 }
 
 impl From<ParseIntError> for Errcode {
-    fn from(e: ParseIntError) -> Errcode { Errcode::ParsingError(format!("Error parsing int: {:?}", e)) }
+    fn from(e: ParseIntError) -> Errcode { Errcode::ParsingError(format!("invalid number: {}", e)) }
 }
 
 impl fmt::Display for Errcode{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}: {}", self.kind(), self.details())
     }
 }
+
+
+
+
+
+#[test]
+fn test_error_messages(){
+    let err = Errcode::WindowNotFound(3, 2);
+    assert_eq!(err.to_string(), "Window not found: no window 3, the session has 2 windows (numbered from 0)");
+    assert_eq!(err.exit_code(), 4);
+
+    let err = Errcode::from(serde_json::from_str::<serde_json::Value>("{\"a\": }").unwrap_err()).in_file(Path::new("/p/s.json"));
+    assert_eq!(err.to_string(), "Invalid JSON: /p/s.json: expected value at line 1 column 7");
+    assert_eq!(err.exit_code(), 5);
+
+    let err = Errcode::from(std::io::Error::from(std::io::ErrorKind::NotFound)).in_file(Path::new("/p/s.json"));
+    assert!(err.to_string().starts_with("File error: /p/s.json: "));
+    assert_eq!(Errcode::ProfileNotFound("api".to_string()).exit_code(), 3);
+    assert_eq!(Errcode::ArgValidationError("start directory").to_string(), "Invalid argument: start directory");
+    assert_eq!(Errcode::UserAborted.in_file(Path::new("x")).exit_code(), 10);
+}
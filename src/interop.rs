@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use dirs::home_dir;
+
+pub mod tmuxinator;
+pub mod tmux_script;
+pub mod kdl;
+pub mod zellij;
+pub mod resurrect;
+
+/// Paths starting with "~" are relative to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            match rest.trim_start_matches('/') {
+                "" => home,
+                rest => home.join(rest),
+            }
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Name of a key that cannot be converted, with the keys containing it
+pub fn key_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
@@ -0,0 +1,376 @@
+use std::fmt;
+
+use nom::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag, take_until, take_while1};
+use nom::character::complete::{char, digit1, line_ending, multispace1, none_of, one_of, space1};
+use nom::combinator::{all_consuming, eof, map, opt, peek, recognize, value};
+use nom::multi::{many0, many0_count};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+
+use crate::errors::Errcode;
+
+/* ------------- KDL documents ------------- */
+// Only the part of KDL used by the Zellij layouts: nodes with arguments, properties and children,
+// strings, raw strings, numbers, booleans, null, comments and "/-" to comment out a node or an entry
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KdlValue {
+    String(String),
+    /// Kept as written
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl KdlValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            KdlValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for KdlValue {
+    fn from(s: &str) -> KdlValue {
+        KdlValue::String(s.to_string())
+    }
+}
+
+impl From<usize> for KdlValue {
+    fn from(n: usize) -> KdlValue {
+        KdlValue::Number(n.to_string())
+    }
+}
+
+impl From<bool> for KdlValue {
+    fn from(b: bool) -> KdlValue {
+        KdlValue::Bool(b)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdlNode {
+    pub name: String,
+    pub args: Vec<KdlValue>,
+    /// In the order they are written
+    pub props: Vec<(String, KdlValue)>,
+    pub children: Vec<KdlNode>,
+}
+
+impl KdlNode {
+    pub fn new(name: &str) -> KdlNode {
+        KdlNode { name: name.to_string(), args: vec![], props: vec![], children: vec![] }
+    }
+
+    pub fn with_arg<V: Into<KdlValue>>(mut self, val: V) -> KdlNode {
+        self.args.push(val.into());
+        self
+    }
+
+    pub fn with_prop<V: Into<KdlValue>>(mut self, key: &str, val: V) -> KdlNode {
+        self.props.push((key.to_string(), val.into()));
+        self
+    }
+
+    pub fn with_child(mut self, child: KdlNode) -> KdlNode {
+        self.children.push(child);
+        self
+    }
+
+    /// The last value given to the property, the way KDL handles duplicated properties
+    pub fn prop(&self, key: &str) -> Option<&KdlValue> {
+        self.props.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&KdlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", identifier(&self.name), indent = indent)?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        for (key, val) in self.props.iter() {
+            write!(f, " {}={}", identifier(key), val)?;
+        }
+        if !self.children.is_empty() {
+            writeln!(f, " {{")?;
+            for child in self.children.iter() {
+                child.write(f, indent + 4)?;
+            }
+            write!(f, "{:indent$}}}", "", indent = indent)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for KdlNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl fmt::Display for KdlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdlValue::String(s) => write!(f, "{}", quote(s)),
+            KdlValue::Number(n) => write!(f, "{}", n),
+            KdlValue::Bool(b) => write!(f, "{}", b),
+            KdlValue::Null => write!(f, "null"),
+        }
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    !c.is_whitespace() && !"\\/(){}<>;[]=,\"".contains(c)
+}
+
+// Names are only quoted when they need to be
+fn identifier(name: &str) -> String {
+    let is_keyword = ["true", "false", "null"].contains(&name);
+    let starts_like_number = name.starts_with(|c: char| c.is_ascii_digit())
+        || ((name.starts_with('-') || name.starts_with('+')) && name[1..].starts_with(|c: char| c.is_ascii_digit()));
+    if !name.is_empty() && name.chars().all(is_identifier_char) && !is_keyword && !starts_like_number {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Parses a whole KDL document
+pub fn parse_kdl(content: &str) -> Result<Vec<KdlNode>, Errcode> {
+    match all_consuming(kdl_nodes)(content) {
+        Ok((_, nodes)) => Ok(nodes),
+        Err(e) => Err(Errcode::ParsingError(format!("Invalid KDL document: {}", Errcode::from(e).details()))),
+    }
+}
+
+/* ------------- KDL parsing ------------- */
+// Format:      NODE := NAME (ARG | KEY=VALUE)* ({ NODE* })? (NEWLINE | ";")
+
+fn line_comment(input: &str) -> IResult<&str, ()> {
+    value((), pair(tag("//"), opt(is_not("\r\n"))))(input)
+}
+
+fn block_comment(input: &str) -> IResult<&str, ()> {
+    value((), tuple((tag("/*"), take_until("*/"), tag("*/"))))(input)
+}
+
+// Space between the entries of a node, a "\" continues the node on the next line
+fn node_space(input: &str) -> IResult<&str, ()> {
+    value((), many0_count(alt((
+        value((), space1),
+        block_comment,
+        value((), tuple((char('\\'), opt(space1), opt(line_comment), line_ending))),
+    ))))(input)
+}
+
+// Space between nodes, also skips the ";" ending nodes
+fn line_space(input: &str) -> IResult<&str, ()> {
+    value((), many0_count(alt((
+        value((), multispace1),
+        line_comment,
+        block_comment,
+        value((), char(';')),
+    ))))(input)
+}
+
+fn escaped_char(input: &str) -> IResult<&str, char> {
+    preceded(char('\\'), alt((
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('/', char('/')),
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        value('\u{8}', char('b')),
+        value('\u{c}', char('f')),
+    )))(input)
+}
+
+fn quoted_string(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(many0(alt((escaped_char, none_of("\\\"")))), |chars| chars.into_iter().collect()),
+        char('"'),
+    )(input)
+}
+
+// Format:      r"..."  or  r#"..."#  with any number of "#"
+fn raw_string(input: &str) -> IResult<&str, String> {
+    let (input, hashes) = preceded(char('r'), recognize(many0_count(char('#'))))(input)?;
+    let (input, _) = char('"')(input)?;
+    let end = format!("\"{}", hashes);
+    let (input, content) = take_until(end.as_str())(input)?;
+    let (input, _) = tag(end.as_str())(input)?;
+    Ok((input, content.to_string()))
+}
+
+fn string(input: &str) -> IResult<&str, String> {
+    alt((quoted_string, raw_string))(input)
+}
+
+fn number(input: &str) -> IResult<&str, String> {
+    map(recognize(tuple((
+        opt(one_of("+-")),
+        digit1,
+        opt(pair(char('.'), digit1)),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    ))), |n: &str| n.to_string())(input)
+}
+
+fn kdl_value(input: &str) -> IResult<&str, KdlValue> {
+    alt((
+        map(string, KdlValue::String),
+        map(number, KdlValue::Number),
+        value(KdlValue::Bool(true), alt((tag("#true"), tag("true")))),
+        value(KdlValue::Bool(false), alt((tag("#false"), tag("false")))),
+        value(KdlValue::Null, alt((tag("#null"), tag("null")))),
+    ))(input)
+}
+
+fn name(input: &str) -> IResult<&str, String> {
+    alt((string, map(take_while1(is_identifier_char), |n: &str| n.to_string())))(input)
+}
+
+#[derive(Clone)]
+enum Entry {
+    Arg(KdlValue),
+    Prop(String, KdlValue),
+    Ignored,
+}
+
+fn entry(input: &str) -> IResult<&str, Entry> {
+    let prop_or_arg = |input| alt((
+        map(tuple((name, char('='), kdl_value)), |(k, _, v)| Entry::Prop(k, v)),
+        map(kdl_value, Entry::Arg),
+    ))(input);
+    alt((
+        value(Entry::Ignored, preceded(pair(tag("/-"), node_space), prop_or_arg)),
+        prop_or_arg,
+    ))(input)
+}
+
+fn children(input: &str) -> IResult<&str, Vec<KdlNode>> {
+    delimited(char('{'), kdl_nodes, char('}'))(input)
+}
+
+// Nodes and children blocks commented out with "/-" are parsed, then dropped
+fn node(input: &str) -> IResult<&str, Option<KdlNode>> {
+    let (input, ignored) = opt(terminated(tag("/-"), node_space))(input)?;
+    let (input, name) = name(input)?;
+    let mut node = KdlNode::new(&name);
+
+    let (input, entries) = many0(preceded(pair(space_or_comment, node_space), entry))(input)?;
+    for e in entries {
+        match e {
+            Entry::Arg(v) => node.args.push(v),
+            Entry::Prop(k, v) => node.props.push((k, v)),
+            Entry::Ignored => {},
+        }
+    }
+
+    let (input, blocks) = many0(preceded(node_space, pair(opt(terminated(tag("/-"), node_space)), children)))(input)?;
+    for (block_ignored, block) in blocks {
+        if block_ignored.is_none() {
+            node.children.extend(block);
+        }
+    }
+
+    let (input, _) = node_space(input)?;
+    let (input, _) = peek(alt((
+        value((), line_ending),
+        value((), one_of(";}")),
+        line_comment,
+        value((), eof),
+    )))(input)?;
+    Ok((input, if ignored.is_some() { None } else { Some(node) }))
+}
+
+// Entries are separated from the name and from each other by some space
+fn space_or_comment(input: &str) -> IResult<&str, ()> {
+    alt((
+        value((), space1),
+        block_comment,
+        value((), peek(char('\\'))),
+    ))(input)
+}
+
+fn kdl_nodes(input: &str) -> IResult<&str, Vec<KdlNode>> {
+    map(
+        terminated(many0(preceded(line_space, node)), line_space),
+        |nodes| nodes.into_iter().flatten().collect(),
+    )(input)
+}
+
+
+
+
+
+#[test]
+fn test_kdl(){
+    let doc = r##"
+// A Zellij layout
+layout {
+    cwd "/home/me"
+    tab name="code" focus=true {
+        pane split_direction="vertical" {
+            pane size="60%" command="nvim" { args "src/main.rs" "-c" r#"echo "q""#; }
+            /-pane size=1
+            pane size=10 /* comment */ borderless=#true
+        }
+    }
+    tab name="logs" \
+        cwd="/var/log"; tab name="with \"quotes\"\n"
+    /-tab { pane; }
+}
+"##;
+    let nodes = parse_kdl(doc).unwrap();
+    assert_eq!(nodes.len(), 1);
+    let layout = &nodes[0];
+    assert_eq!(layout.children.len(), 4);
+    assert_eq!(layout.children[0], KdlNode::new("cwd").with_arg("/home/me"));
+
+    let code = &layout.children[1];
+    assert_eq!(code.prop("name"), Some(&KdlValue::from("code")));
+    assert_eq!(code.prop("focus"), Some(&KdlValue::Bool(true)));
+    let split = &code.children[0];
+    assert_eq!(split.children.len(), 2);
+    assert_eq!(split.children[0].child("args").unwrap().args,
+        vec![KdlValue::from("src/main.rs"), KdlValue::from("-c"), KdlValue::from("echo \"q\"")]);
+    assert_eq!(split.children[1].props, vec![
+        ("size".to_string(), KdlValue::from(10)),
+        ("borderless".to_string(), KdlValue::Bool(true)),
+    ]);
+    assert_eq!(layout.children[2].prop("cwd"), Some(&KdlValue::from("/var/log")));
+    assert_eq!(layout.children[3].prop("name"), Some(&KdlValue::from("with \"quotes\"\n")));
+
+    // What is written is read back the same
+    let written: String = nodes.iter().map(|n| n.to_string()).collect();
+    assert_eq!(parse_kdl(&written).unwrap(), nodes);
+    assert_eq!(KdlNode::new("pane").with_prop("size", "50%").with_child(KdlNode::new("args").with_arg("a b")).to_string(),
+        "pane size=\"50%\" {\n    args \"a b\"\n}\n");
+
+    for wrong in ["layout {", "pane size=", "pane \"unfinished", "a }"].iter() {
+        assert!(parse_kdl(wrong).is_err(), "{}", wrong);
+    }
+}
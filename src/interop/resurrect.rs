@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dirs::home_dir;
+
+use crate::errors::Errcode;
+use crate::layout::TmuxLayout;
+use crate::pane::{PaneSerializer, TmuxPane};
+use crate::procs::CommandFilter;
+use crate::session::TmuxSession;
+use crate::tmux::WindowInfo;
+use crate::window::TmuxWindow;
+
+// Relative to the home directory, tmux-resurrect uses the first one if it exists
+const SAVE_FILES: [&str; 2] = [".tmux/resurrect/last", ".local/share/tmux/resurrect/last"];
+
+const FIELDS_SEP: &str = "\t";
+// Fields that can be empty are written with a ":" in front of them
+const FIELD_PREFIX: char = ':';
+
+/// The last save of tmux-resurrect
+pub fn find_save_file() -> Result<PathBuf, Errcode> {
+    let home = home_dir().ok_or(Errcode::HomeDirNotFound)?;
+    SAVE_FILES.iter().map(|f| home.join(f)).find(|f| f.is_file())
+        .ok_or_else(|| Errcode::FileError("No tmux-resurrect save found".to_string()))
+}
+
+fn field(s: &str) -> &str {
+    s.strip_prefix(FIELD_PREFIX).unwrap_or(s)
+}
+
+// The spaces of the paths are escaped
+fn path_field(s: &str) -> PathBuf {
+    PathBuf::from(field(s).replace("\\ ", " "))
+}
+
+fn record_fields(line: &str, nfields: usize) -> Result<Vec<&str>, Errcode> {
+    let fields: Vec<&str> = line.splitn(nfields, FIELDS_SEP).collect();
+    if fields.len() < nfields - 1 {
+        return Err(Errcode::ParsingError(format!("Invalid tmux-resurrect record: \"{}\"", line)));
+    }
+    Ok(fields)
+}
+
+/// A window saved by tmux-resurrect, with the session it belongs to
+// Format:      window  SESSION  INDEX  :NAME  ACTIVE  :FLAGS  LAYOUT  [AUTOMATIC_RENAME]
+fn parse_window(line: &str) -> Result<(String, WindowInfo), Errcode> {
+    let f = record_fields(line, 8)?;
+    Ok((f[1].to_string(), WindowInfo {
+        index: usize::from_str(f[2])?,
+        active: f[4] == "1",
+        // Not set on the window when it follows the global option, on by default
+        automatic_rename: f.get(7).map(|r| field(r) != "off").unwrap_or(true),
+        layout: f[6].to_string(),
+        name: field(f[3]).to_string(),
+    }))
+}
+
+/// A pane saved by tmux-resurrect
+#[derive(Debug, Clone, PartialEq)]
+struct SavedPane {
+    session: String,
+    window_index: usize,
+    index: usize,
+    active: bool,
+    path: PathBuf,
+    full_command: String,
+}
+
+impl FromStr for SavedPane {
+    type Err = Errcode;
+
+    // Format:      pane  SESSION  WINDOW_INDEX  WINDOW_ACTIVE  :WINDOW_FLAGS  INDEX  [TITLE]  :PATH  ACTIVE  COMMAND  :FULL_COMMAND
+    // The title is missing from the files of the older versions, told apart by the path
+    // being the 8th field, as the command may contain tabs
+    fn from_str(line: &str) -> Result<SavedPane, Errcode> {
+        let mut f = record_fields(line, 11)?;
+        if f.get(7).map(|p| p.starts_with(FIELD_PREFIX)).unwrap_or(false) {
+            f.remove(6);
+        } else {
+            f = record_fields(line, 10)?;
+        }
+        Ok(SavedPane {
+            session: f[1].to_string(),
+            window_index: usize::from_str(f[2])?,
+            index: usize::from_str(f[5])?,
+            active: f[7] == "1",
+            path: path_field(f[6]),
+            full_command: field(f[9]).to_string(),
+        })
+    }
+}
+
+impl SavedPane {
+    // The arguments of the command are saved without their quotes
+    fn pane(&self, window_dir: &Path, filter: &CommandFilter) -> TmuxPane {
+        let argv: Vec<String> = self.full_command.split_whitespace().map(|a| a.to_string()).collect();
+        let cmd = if filter.accepts(&argv) { argv.join(" ") } else { String::new() };
+        let mut pane = TmuxPane::from_cmd(cmd);
+        if self.path != window_dir {
+            pane.start_directory = Some(self.path.clone());
+        }
+        pane
+    }
+}
+
+/// Converts the sessions saved by tmux-resurrect to tmuxp sessions
+#[derive(Default)]
+pub struct ResurrectImport {
+    /// Panes only running a shell are imported as blank panes
+    filter: CommandFilter,
+    /// Records without any equivalent, left out of the import
+    pub unmapped: Vec<String>,
+}
+
+impl ResurrectImport {
+    /// One session per tmux session of the save, in the order of the file
+    pub fn import(&mut self, content: &str) -> Result<Vec<TmuxSession>, Errcode> {
+        let mut windows: Vec<(String, WindowInfo)> = vec![];
+        let mut panes: Vec<SavedPane> = vec![];
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            match line.split(FIELDS_SEP).next().unwrap_or_default() {
+                "window" => windows.push(parse_window(line)?),
+                "pane" => panes.push(SavedPane::from_str(line)?),
+                // The session attached to the client
+                "state" => {},
+                kind => if !self.unmapped.iter().any(|k| k == kind) {
+                    self.unmapped.push(kind.to_string());
+                },
+            }
+        }
+
+        let mut names: Vec<&String> = vec![];
+        for (name, _) in windows.iter() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names.iter().map(|name| {
+            let mut infos: Vec<&WindowInfo> = windows.iter().filter(|(s, _)| s == *name).map(|(_, w)| w).collect();
+            infos.sort_by_key(|w| w.index);
+            let wins = infos.iter()
+                .map(|info| self.import_window(info, panes.iter().filter(|p| &p.session == *name).collect()))
+                .collect::<Result<Vec<TmuxWindow>, Errcode>>()?;
+            let start_directory = wins[0].start_directory.clone().unwrap_or_default();
+            Ok(TmuxSession::new(name.to_string(), start_directory, wins))
+        }).collect()
+    }
+
+    fn import_window(&self, info: &WindowInfo, session_panes: Vec<&SavedPane>) -> Result<TmuxWindow, Errcode> {
+        let mut win_panes: Vec<&SavedPane> = session_panes.into_iter().filter(|p| p.window_index == info.index).collect();
+        win_panes.sort_by_key(|p| p.index);
+        if win_panes.is_empty() {
+            return Err(Errcode::ParsingError(format!("No pane saved for window {}", info.index)));
+        }
+
+        let window_dir = win_panes[0].path.clone();
+        let mut win = TmuxWindow::default(window_dir.clone());
+        win.window_name = info.name.clone();
+        win.window_index = Some(info.index);
+        win.focus = info.active;
+        win.set_automatic_rename(info.automatic_rename);
+        win.layout = Some(TmuxLayout::from_str(&info.layout)?);
+        win.panes = PaneSerializer::from_panes(
+            win_panes.iter().map(|p| p.pane(&window_dir, &self.filter)).collect(),
+            win_panes.iter().position(|p| p.active).unwrap_or(0),
+        );
+        Ok(win)
+    }
+}
+
+
+
+
+
+#[test]
+fn test_import_resurrect(){
+    let save = [
+        "pane\twork\t1\t0\t:-\t0\thost\t:/home/me/proj\t1\tbash\t:",
+        "pane\twork\t2\t1\t:*\t0\thost\t:/home/me/proj/src\t0\tnvim\t:nvim src/main.rs",
+        "pane\twork\t2\t1\t:*\t2\thost\t:/home/me/proj/src\t0\tbash\t:bash",
+        "pane\twork\t2\t1\t:*\t1\thost\t:/home/me/My\\ Notes\t1\tcargo\t:cargo watch -x test",
+        // Older versions don't save the title of the panes
+        "pane\tmisc\t0\t1\t:*\t0\t:/tmp\t1\thtop\t:htop -d 10",
+        "pane\tmisc\t1\t0\t:-\t0\t:/tmp\t1\tgrep\t:grep -P a\tb log",
+        "window\twork\t1\t:bash\t0\t:-\t5be4,211x62,0,0,15\t:",
+        "window\twork\t2\t:code: main\t1\t:*\tdcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}\toff",
+        "window\tmisc\t0\t:top\t1\t:*\t5be4,211x62,0,0,15",
+        "window\tmisc\t1\t:grep\t0\t:-\t5be4,211x62,0,0,15",
+        "grouped_session\tview\twork\t:2\t:1",
+        "state\twork\tmisc",
+    ].join("\n");
+
+    let mut import = ResurrectImport::default();
+    let mut sessions = import.import(&save).unwrap();
+    assert_eq!(import.unmapped, vec!["grouped_session"]);
+    assert_eq!(sessions.iter().map(|s| s.name().to_string()).collect::<Vec<String>>(), vec!["work", "misc"]);
+
+    let work = &mut sessions[0];
+    assert_eq!(work.start_directory(), Path::new("/home/me/proj"));
+    assert_eq!(work.windows.len(), 2);
+    let shell = work.get_window_ref(0).unwrap();
+    assert_eq!((shell.window_name.as_str(), shell.window_index, shell.focus), ("bash", Some(1), false));
+    assert_eq!(shell.panes.get_panes_cmds().unwrap(), vec![""]);
+    let code = work.get_window_ref(1).unwrap();
+    assert_eq!((code.window_name.as_str(), code.window_index, code.focus), ("code: main", Some(2), true));
+    assert_eq!(code.options.get_flag(crate::window::AUTOMATIC_RENAME), Some(false));
+    assert_eq!(code.start_directory, Some(PathBuf::from("/home/me/proj/src")));
+    assert_eq!(code.layout.as_ref().unwrap().nb_panes(), 3);
+    assert_eq!(code.panes.get_panes_cmds().unwrap(), vec!["nvim src/main.rs", "cargo watch -x test", ""]);
+    let start_dirs: Vec<Option<PathBuf>> = code.panes.get_panes().into_iter().map(|p| p.start_directory).collect();
+    assert_eq!(start_dirs, vec![None, Some(PathBuf::from("/home/me/My Notes")), None]);
+    assert_eq!(code.panes.focused_index(), 1);
+
+    let top = sessions[1].get_window_ref(0).unwrap();
+    assert_eq!(top.panes.get_panes_cmds().unwrap(), vec!["htop -d 10"]);
+    assert_eq!(top.start_directory, Some(PathBuf::from("/tmp")));
+    let grep = sessions[1].get_window_ref(1).unwrap();
+    assert_eq!(grep.panes.get_panes_cmds().unwrap(), vec!["grep -P a b log"]);
+    assert_eq!(grep.start_directory, Some(PathBuf::from("/tmp")));
+
+    assert!(ResurrectImport::default().import("pane\twork\t1").is_err());
+    assert!(ResurrectImport::default().import("window\twork\t1\t:bash\t0\t:-\t5be4,211x62,0,0,15").is_err());
+}
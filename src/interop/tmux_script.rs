@@ -0,0 +1,240 @@
+use serde_json::Value;
+
+use std::path::{Path, PathBuf};
+
+use crate::layout::preset::LayoutPreset;
+use crate::options::TmuxOptions;
+use crate::pane::TmuxPane;
+use crate::serialisation::shell_quote;
+use crate::session::TmuxSession;
+use crate::window::TmuxWindow;
+
+// Commands starting with a space are left out of the shell history (if HISTCONTROL allows it)
+const HISTORY_SKIP: &str = " ";
+
+// Shell variables holding the ids tmux gives to the windows and panes
+fn window_var(win: usize) -> String {
+    format!("\"$W{}\"", win)
+}
+
+fn pane_var(win: usize, pane: usize) -> String {
+    format!("\"$P{}_{}\"", win, pane)
+}
+
+fn path_arg(path: &Path) -> String {
+    shell_quote(&path.to_string_lossy())
+}
+
+// Relative directories of the panes are relative to the one of their window
+fn pane_dir(window_dir: &Path, pane: &TmuxPane) -> PathBuf {
+    match &pane.start_directory {
+        Some(dir) => window_dir.join(dir),
+        None => window_dir.to_path_buf(),
+    }
+}
+
+fn option_value(val: &Value) -> String {
+    match val {
+        Value::String(s) => s.clone(),
+        _ => val.to_string(),
+    }
+}
+
+/// Generates a POSIX shell script recreating the session with tmux only, for the machines without tmuxp
+pub struct TmuxScript<'a> {
+    session: &'a TmuxSession,
+    lines: Vec<String>,
+}
+
+impl<'a> TmuxScript<'a> {
+    pub fn new(session: &'a TmuxSession) -> TmuxScript<'a> {
+        TmuxScript { session, lines: vec![] }
+    }
+
+    fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    fn set_options(&mut self, cmd: &str, target: &str, options: &TmuxOptions) {
+        for (name, val) in options.iter() {
+            self.push(format!("tmux {} {} {} {}", cmd, target, shell_quote(name), shell_quote(&option_value(val))));
+        }
+    }
+
+    pub fn generate(mut self) -> String {
+        let ses = self.session;
+        self.push("#!/bin/sh".to_string());
+        self.push(format!("# Recreates the tmux session \"{}\"", ses.name()));
+        self.push("set -e".to_string());
+        self.push(String::new());
+        if let Some(socket) = &ses.socket_name {
+            self.push(format!("tmux() {{ command tmux -L {} \"$@\"; }}", shell_quote(socket)));
+        }
+        self.push(format!("SESSION={}", shell_quote(ses.name())));
+        self.push(String::new());
+        // Inside tmux, attaching would nest the session in the current one
+        self.push("attach() {".to_string());
+        self.push("    if [ -n \"$TMUX\" ]; then".to_string());
+        self.push("        tmux switch-client -t \"=$SESSION\"".to_string());
+        self.push("    else".to_string());
+        self.push("        tmux attach-session -t \"=$SESSION\"".to_string());
+        self.push("    fi".to_string());
+        self.push("}".to_string());
+        self.push(String::new());
+        self.push("if tmux has-session -t \"=$SESSION\" 2>/dev/null; then".to_string());
+        self.push("    attach".to_string());
+        self.push("    exit 0".to_string());
+        self.push("fi".to_string());
+        self.push(String::new());
+
+        self.push(format!("cd {}", path_arg(ses.start_directory())));
+        if let Some(script) = &ses.before_script {
+            self.push(script.clone());
+        }
+        self.set_options("set-option", "-g", &ses.global_options);
+
+        for (n, win) in ses.windows.iter().enumerate() {
+            self.push(String::new());
+            self.push(format!("# Window {}: {}", n, win.window_name));
+            self.create_window(n, win);
+            if n == 0 {
+                self.set_options("set-option", "-t \"$S\"", &ses.options);
+            }
+            self.set_options("set-window-option", &format!("-t {}", window_var(n)), &win.options);
+            self.create_panes(n, win);
+            self.set_options("set-window-option", &format!("-t {}", window_var(n)), &win.options_after);
+            self.push(format!("tmux select-pane -t {}", pane_var(n, win.panes.focused_index())));
+        }
+
+        self.push(String::new());
+        if let Some(focus) = ses.windows.iter().position(|w| w.focus) {
+            self.push(format!("tmux select-window -t {}", window_var(focus)));
+        }
+        self.push("attach".to_string());
+        let mut script = self.lines.join("\n");
+        script.push('\n');
+        script
+    }
+
+    fn create_window(&mut self, n: usize, win: &TmuxWindow) {
+        let ses = self.session;
+        // The first pane is created with the window
+        let dir = pane_dir(&win.directory(ses.start_directory()), &win.panes.get_panes()[0]);
+        let mut args = format!("-d -P -F '#{{window_id}}' -n {} -c {}", shell_quote(&win.window_name), path_arg(&dir));
+        if n == 0 {
+            // The first window is created with the session, the size of its layout avoids resizing it
+            if let Some(layout) = &win.layout {
+                let size = layout.size();
+                args.push_str(&format!(" -x {} -y {}", size.width, size.height));
+            }
+            for (var, val) in ses.environment.iter() {
+                args.push_str(&format!(" -e {}", shell_quote(&format!("{}={}", var, option_value(val)))));
+            }
+            self.push(format!("W0=$(tmux new-session -s \"$SESSION\" {})", args));
+            // The id of the session is a safer target than its name, which may contain "." or ":"
+            self.push("S=$(tmux display-message -p -t \"$W0\" '#{session_id}')".to_string());
+            // tmux refuses to move a window to the index it already has
+            if let Some(index) = win.window_index {
+                self.push(format!("[ \"$(tmux display-message -p -t \"$W0\" '#{{window_index}}')\" = {} ] || tmux move-window -s \"$W0\" -t \"$S:{}\"",
+                    index, index));
+            }
+        } else {
+            let target = win.window_index.map(|i| i.to_string()).unwrap_or_default();
+            self.push(format!("W{}=$(tmux new-window -t \"$S:{}\" {})", n, target, args));
+        }
+    }
+
+    fn create_panes(&mut self, n: usize, win: &TmuxWindow) {
+        let ses = self.session;
+        let window_dir = win.directory(ses.start_directory());
+        let panes = win.panes.get_panes();
+
+        self.push(format!("P{}_0=$(tmux display-message -p -t {} '#{{pane_id}}')", n, window_var(n)));
+        for (p, pane) in panes.iter().enumerate().skip(1) {
+            // Splitting the last pane keeps the panes in order, tiling leaves room for the next split
+            self.push(format!("P{}_{}=$(tmux split-window -d -t {} -P -F '#{{pane_id}}' -c {})",
+                n, p, pane_var(n, p - 1), path_arg(&pane_dir(&window_dir, pane))));
+            self.push(format!("tmux select-layout -t {} tiled", window_var(n)));
+        }
+        match &win.layout {
+            Some(layout) => self.push(format!("tmux select-layout -t {} {}", window_var(n), shell_quote(&layout.to_string()))),
+            None => self.push(format!("tmux select-layout -t {} {}", window_var(n),
+                win.layout_preset.unwrap_or(LayoutPreset::EvenVertical))),
+        }
+
+        let prefix = if ses.suppress_history.unwrap_or(false) { HISTORY_SKIP } else { "" };
+        for (p, pane) in panes.iter().enumerate() {
+            if let Some(secs) = pane.sleep_before {
+                self.push(format!("sleep {}", secs));
+            }
+            let cmds = ses.shell_command_before.iter().chain(win.shell_command_before.iter()).chain(pane.shell_command.iter());
+            for cmd in cmds {
+                self.send_keys(n, p, &format!("{}{}", prefix, cmd), pane.enter.unwrap_or(true));
+            }
+            if let Some(secs) = pane.sleep_after {
+                self.push(format!("sleep {}", secs));
+            }
+        }
+    }
+
+    fn send_keys(&mut self, win: usize, pane: usize, cmd: &str, enter: bool) {
+        // -l sends the text as it is, without looking for key names in it
+        self.push(format!("tmux send-keys -t {} -l {}", pane_var(win, pane), shell_quote(cmd)));
+        if enter {
+            self.push(format!("tmux send-keys -t {} Enter", pane_var(win, pane)));
+        }
+    }
+}
+
+
+
+
+
+#[test]
+fn test_tmux_script(){
+    use crate::serialisation::FileFormat;
+
+    let session = TmuxSession::from_str(r#"{
+        "session_name": "api",
+        "start_directory": "/srv/api",
+        "windows": [
+            {
+                "window_name": "code",
+                "window_index": 0,
+                "layout": "020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}",
+                "start_directory": "src",
+                "panes": ["nvim", {"shell_command": "cargo test", "focus": "true", "start_directory": "/tmp", "enter": false}],
+                "options": {"automatic-rename": "off"}
+            },
+            {"window_name": "logs", "window_index": 5, "focus": "true", "start_directory": "/var/log", "panes": ["tail -f 'app log'"]},
+            {"window_name": "shell", "panes": ["htop", "ls"]}
+        ],
+        "socket_name": "work",
+        "suppress_history": true,
+        "shell_command_before": ["source venv/bin/activate"],
+        "environment": {"RUST_LOG": "debug"}
+    }"#, FileFormat::Json).unwrap();
+    let script = TmuxScript::new(&session).generate();
+    let lines: Vec<&str> = script.lines().collect();
+    let position = |line: &str| lines.iter().position(|l| *l == line).unwrap_or_else(|| panic!("No line {}", line));
+
+    assert_eq!(lines[0], "#!/bin/sh");
+    position("tmux() { command tmux -L work \"$@\"; }");
+    let new_session = position("W0=$(tmux new-session -s \"$SESSION\" -d -P -F '#{window_id}' -n code -c /srv/api/src -x 80 -y 24 -e RUST_LOG=debug)");
+    let move_window = position("[ \"$(tmux display-message -p -t \"$W0\" '#{window_index}')\" = 0 ] || tmux move-window -s \"$W0\" -t \"$S:0\"");
+    let split = position("P0_1=$(tmux split-window -d -t \"$P0_0\" -P -F '#{pane_id}' -c /tmp)");
+    let layout = position("tmux select-layout -t \"$W0\" '020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}'");
+    let before = position("tmux send-keys -t \"$P0_1\" -l ' source venv/bin/activate'");
+    let cmd = position("tmux send-keys -t \"$P0_1\" -l ' cargo test'");
+    assert_ne!(lines[cmd + 1], "tmux send-keys -t \"$P0_1\" Enter");
+    let focus_pane = position("tmux select-pane -t \"$P0_1\"");
+    assert!(new_session < move_window && move_window < split && split < layout && layout < before && before < cmd && cmd < focus_pane);
+
+    position("W1=$(tmux new-window -t \"$S:5\" -d -P -F '#{window_id}' -n logs -c /var/log)");
+    position("tmux send-keys -t \"$P1_0\" -l ' tail -f '\\''app log'\\'''");
+    // Without a directory of its own, a window starts in the one of the session
+    position("W2=$(tmux new-window -t \"$S:\" -d -P -F '#{window_id}' -n shell -c /srv/api)");
+    position("P2_1=$(tmux split-window -d -t \"$P2_0\" -P -F '#{pane_id}' -c /srv/api)");
+    assert_eq!(lines[lines.len() - 2], "tmux select-window -t \"$W1\"");
+    assert_eq!(lines[lines.len() - 1], "attach");
+}
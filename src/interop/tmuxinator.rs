@@ -0,0 +1,306 @@
+use serde_json::{Map, Value};
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dirs::home_dir;
+
+use crate::errors::Errcode;
+use crate::interop::{expand_home, key_path};
+use crate::layout::TmuxLayout;
+use crate::layout::preset::LayoutPreset;
+use crate::pane::{commands_from_json, PaneSerializer, TmuxPane};
+use crate::serialisation::{shell_quote, FileFormat};
+use crate::session::TmuxSession;
+use crate::window::TmuxWindow;
+
+// Relative to the home directory, the first one is the current location
+const PROJECT_DIRS: [&str; 2] = [".config/tmuxinator", ".tmuxinator"];
+
+const SYNCHRONIZE_PANES: &str = "synchronize-panes";
+
+/// The file of a tmuxinator project, from its path or its name
+pub fn find_project(project: &str) -> Result<PathBuf, Errcode> {
+    let path = expand_home(project);
+    if path.is_file() {
+        return Ok(path);
+    }
+    let home = home_dir().ok_or(Errcode::HomeDirNotFound)?;
+    for dir in PROJECT_DIRS.iter() {
+        for ext in FileFormat::Yaml.extensions() {
+            let fname = home.join(dir).join(format!("{}.{}", project, ext));
+            if fname.is_file() {
+                return Ok(fname);
+            }
+        }
+    }
+    Err(Errcode::FileError(format!("No tmuxinator project \"{}\" found", project)))
+}
+
+// Names and paths can be written as numbers in YAML
+fn scalar_to_string(val: &Value) -> Result<String, Errcode> {
+    match val {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        _ => Err(Errcode::YamlError(format!("Expected a string, got {}", val))),
+    }
+}
+
+/// tmuxp runs the before_script without a shell, commands that need one are wrapped in "sh -c"
+fn before_script(cmds: &[String]) -> String {
+    let script = cmds.join(" && ");
+    if script.split_whitespace().all(|w| shell_quote(w) == w) {
+        script
+    } else {
+        format!("sh -c {}", shell_quote(&script))
+    }
+}
+
+/// Converts tmuxinator projects to tmuxp sessions
+#[derive(Default)]
+pub struct TmuxinatorImport {
+    /// Keys of the project without any tmuxp equivalent, left out of the session
+    pub unmapped: Vec<String>,
+}
+
+impl TmuxinatorImport {
+    // Format:      name: NAME
+    //              root: DIR
+    //              pre_window: CMD | [CMD, ...]
+    //              on_project_start: CMD | [CMD, ...]
+    //              windows:
+    //                - NAME: CMD | [CMD, ...] | {root: DIR, layout: LAYOUT, pre: CMDS, panes: [PANE, ...]}
+    pub fn import(&mut self, content: &str) -> Result<TmuxSession, Errcode> {
+        let project: Value = FileFormat::Yaml.deserialize(content)?;
+        let project = project.as_object()
+            .ok_or_else(|| Errcode::YamlError("A tmuxinator project is a map".to_string()))?;
+
+        let name = match project.get("name").or_else(|| project.get("project_name")) {
+            Some(n) => scalar_to_string(n)?,
+            None => return Err(Errcode::YamlError("The tmuxinator project has no name".to_string())),
+        };
+        // Tmuxinator starts the project from the current directory if it has no root
+        let root = match project.get("root").or_else(|| project.get("project_root")) {
+            Some(r) => expand_home(&scalar_to_string(r)?),
+            None => std::env::current_dir()?,
+        };
+
+        let mut session = TmuxSession::new(name, root.clone(), vec![]);
+        let (mut startup_window, mut startup_pane) = (None, None);
+        for (key, val) in project.iter() {
+            match key.as_str() {
+                "name" | "project_name" | "root" | "project_root" => {},
+                "windows" | "tabs" => session.windows = self.windows(key, val, &root)?,
+                "socket_name" => session.socket_name = Some(scalar_to_string(val)?),
+                // "pre" is the deprecated name of "on_project_start"
+                "on_project_start" | "pre" => session.before_script = Some(before_script(&commands_from_json(val)?)),
+                "pre_window" | "pre_tab" => session.shell_command_before = commands_from_json(val)?,
+                "startup_window" => startup_window = Some(val.clone()),
+                "startup_pane" => startup_pane = Some(val.as_u64()
+                    .ok_or_else(|| Errcode::YamlError(format!("Invalid startup_pane {}", val)))? as usize),
+                _ => self.unmapped.push(key.clone()),
+            }
+        }
+
+        if session.windows.is_empty() {
+            session.windows.push(TmuxWindow::default(root));
+        }
+        // Windows are given by their name or by their tmux index, which depends on the base-index of tmux
+        let focus = match &startup_window {
+            None => Some(0),
+            Some(Value::Number(_)) => None,
+            Some(w) => {
+                let wname = scalar_to_string(w)?;
+                Some(session.windows.iter().position(|win| win.window_name == wname)
+                    .ok_or_else(|| Errcode::YamlError(format!("startup_window: no window \"{}\"", wname)))?)
+            },
+        };
+        session.set_window_focus(focus.unwrap_or(0))?;
+        let focus = match focus {
+            Some(focus) => focus,
+            None => {
+                self.unmapped.push("startup_window".to_string());
+                if startup_pane.is_some() {
+                    self.unmapped.push("startup_pane".to_string());
+                }
+                return Ok(session);
+            },
+        };
+        if let Some(pane) = startup_pane {
+            let win = session.get_window_ref(focus)?;
+            if pane >= win.panes.nb_panes() {
+                return Err(Errcode::YamlError(format!("startup_pane: no pane {} in window {}", pane, focus)));
+            }
+            win.panes = PaneSerializer::from_panes(win.panes.get_panes(), pane);
+        }
+        Ok(session)
+    }
+
+    fn windows(&mut self, key: &str, val: &Value, root: &Path) -> Result<Vec<TmuxWindow>, Errcode> {
+        let list = val.as_array()
+            .ok_or_else(|| Errcode::YamlError(format!("{} is not a list of windows", key)))?;
+        let mut windows = vec![];
+        for (n, win) in list.iter().enumerate() {
+            let (name, descr) = win.as_object()
+                .filter(|w| w.len() == 1)
+                .and_then(|w| w.iter().next())
+                .ok_or_else(|| Errcode::YamlError(format!("Window {} is not a map from its name to its content", n)))?;
+            windows.push(self.window(&key_path(key, name), name, descr, root)?);
+        }
+        Ok(windows)
+    }
+
+    fn window(&mut self, path: &str, name: &str, descr: &Value, root: &Path) -> Result<TmuxWindow, Errcode> {
+        let mut win = TmuxWindow::default(root.to_path_buf());
+        win.window_name = name.to_string();
+        // tmux doesn't rename the windows created with a name
+        win.set_automatic_rename(false);
+
+        let settings = match descr {
+            Value::Object(settings) => settings.clone(),
+            // The commands of the only pane of the window
+            _ => {
+                let mut settings = Map::new();
+                settings.insert("panes".to_string(), Value::Array(vec![descr.clone()]));
+                settings
+            },
+        };
+
+        let mut layout = None;
+        let mut panes = vec![TmuxPane::default()];
+        for (key, val) in settings.iter() {
+            match key.as_str() {
+                "root" => win.start_directory = Some(root.join(expand_home(&scalar_to_string(val)?))),
+                "layout" => layout = Some(scalar_to_string(val)?),
+                "panes" => panes = self.panes(&key_path(path, key), val)?,
+                "pre" => win.shell_command_before = commands_from_json(val)?,
+                "synchronize" => match val {
+                    Value::Bool(false) => {},
+                    Value::Bool(true) => win.options.set_flag(SYNCHRONIZE_PANES, true),
+                    Value::String(s) if s == "before" => win.options.set_flag(SYNCHRONIZE_PANES, true),
+                    Value::String(s) if s == "after" => win.options_after.set_flag(SYNCHRONIZE_PANES, true),
+                    _ => self.unmapped.push(key_path(path, key)),
+                },
+                _ => self.unmapped.push(key_path(path, key)),
+            }
+        }
+
+        if let Some(layout) = layout {
+            self.layout(&mut win, &key_path(path, "layout"), &layout, panes.len());
+        }
+        win.panes = PaneSerializer::from_panes(panes, 0);
+        Ok(win)
+    }
+
+    // Format:      CMD | null | [CMD, ...] | {TITLE: CMD | [CMD, ...]}
+    fn panes(&mut self, path: &str, val: &Value) -> Result<Vec<TmuxPane>, Errcode> {
+        let list = match val {
+            Value::Array(list) => list.clone(),
+            Value::Null => vec![],
+            _ => vec![val.clone()],
+        };
+        let mut panes = vec![];
+        for (n, pane) in list.iter().enumerate() {
+            let cmds = match pane {
+                Value::Object(named) if named.len() == 1 => {
+                    let (title, cmds) = named.iter().next().unwrap();
+                    // tmuxp has no pane titles
+                    self.unmapped.push(key_path(&key_path(path, &n.to_string()), title));
+                    commands_from_json(cmds)?
+                },
+                _ => commands_from_json(pane)?,
+            };
+            panes.push(TmuxPane { shell_command: cmds, ..TmuxPane::default() });
+        }
+        if panes.is_empty() {
+            panes.push(TmuxPane::default());
+        }
+        Ok(panes)
+    }
+
+    // Either a tmux preset, or a layout copied from tmux
+    fn layout(&mut self, win: &mut TmuxWindow, path: &str, layout: &str, npanes: usize) {
+        if let Ok(preset) = LayoutPreset::from_str(layout) {
+            win.layout_preset = Some(preset);
+            return;
+        }
+        match TmuxLayout::from_str(layout) {
+            Ok(l) if l.nb_panes() == npanes => win.layout = Some(l),
+            _ => self.unmapped.push(path.to_string()),
+        }
+    }
+}
+
+
+
+
+
+#[test]
+fn test_import_tmuxinator(){
+    let project = r#"
+name: api
+root: /srv/api
+socket_name: work
+on_project_start: docker compose up -d && sleep 2
+pre_window: source venv/bin/activate
+tmux_options: -f ~/.tmux.other.conf
+startup_window: logs
+startup_pane: 1
+windows:
+  - editor:
+      root: src
+      layout: main-vertical
+      pre: export RUST_LOG=debug
+      synchronize: after
+      panes:
+        - nvim
+        -
+        - tests:
+          - cargo build
+          - cargo test
+  - server: cargo run
+  - logs:
+      layout: 020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}
+      panes: [tail -f api.log, htop]
+      on_close: echo bye
+  - blank:
+"#;
+    let mut import = TmuxinatorImport::default();
+    let session = import.import(project).unwrap();
+    assert_eq!(import.unmapped, vec!["tmux_options", "windows.editor.panes.2.tests", "windows.logs.on_close"]);
+    assert_eq!(session.socket_name, Some("work".to_string()));
+    assert_eq!(session.before_script, Some("sh -c 'docker compose up -d && sleep 2'".to_string()));
+    assert_eq!(session.shell_command_before, vec!["source venv/bin/activate"]);
+    assert_eq!(session.windows.len(), 4);
+
+    let editor = &session.windows[0];
+    assert_eq!(editor.window_name, "editor");
+    assert_eq!(editor.start_directory, Some(PathBuf::from("/srv/api/src")));
+    assert_eq!(editor.shell_command_before, vec!["export RUST_LOG=debug"]);
+    assert_eq!(editor.options_after.get_flag(SYNCHRONIZE_PANES), Some(true));
+    assert_eq!(editor.options.get_flag("automatic-rename"), Some(false));
+    assert!(editor.layout.is_none());
+    assert_eq!(editor.layout_preset, Some(LayoutPreset::MainVertical));
+    let panes = editor.panes.get_panes();
+    assert_eq!(panes[0].shell_command, vec!["nvim"]);
+    assert!(panes[1].shell_command.is_empty());
+    assert_eq!(panes[2].shell_command, vec!["cargo build", "cargo test"]);
+
+    assert_eq!(session.windows[1].panes.get_panes_cmds().unwrap(), vec!["cargo run"]);
+    assert!(session.windows[1].layout.is_none());
+    let logs = &session.windows[2];
+    assert!(logs.focus && !session.windows[0].focus);
+    assert_eq!(logs.panes.focused_index(), 1);
+    assert_eq!(logs.layout.as_ref().unwrap().to_string(), "020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}");
+    assert_eq!(session.windows[3].panes.get_panes_cmds().unwrap(), vec![""]);
+
+    // The index of a window depends on the base-index of tmux, the first window is focused
+    let mut import = TmuxinatorImport::default();
+    let session = import.import("name: x\nstartup_window: 1\nstartup_pane: 1\nwindows:\n  - a: ls\n  - b: [ls, htop]").unwrap();
+    assert_eq!(import.unmapped, vec!["startup_window", "startup_pane"]);
+    assert!(session.windows[0].focus && !session.windows[1].focus);
+    assert_eq!(session.windows[1].panes.focused_index(), 0);
+
+    assert!(import.import("windows: []").is_err());
+    assert!(import.import("name: x\nwindows:\n  - a: ls\n    b: ls").is_err());
+}
@@ -0,0 +1,398 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::errors::Errcode;
+use crate::interop::key_path;
+use crate::interop::kdl::{parse_kdl, KdlNode, KdlValue};
+use crate::layout::{CellSize, LayoutContent, LayoutNode, LayoutSpec, SplitDirection, TermSize};
+use crate::pane::{PaneSerializer, TmuxPane};
+use crate::procs::SHELLS;
+use crate::serialisation::shell_quote;
+use crate::session::TmuxSession;
+use crate::window::{TmuxWindow, AUTOMATIC_RENAME};
+
+// Zellij runs programs, commands that need a shell are run with "sh -c"
+const SHELL: &str = "sh";
+const SHELL_CMD_FLAG: &str = "-c";
+const CMDS_SEP: &str = "; ";
+
+// Zellij names a split after the line between the panes, tmux after the way the panes are arranged
+fn zellij_direction(dir: SplitDirection) -> &'static str {
+    match dir {
+        SplitDirection::Horizontal => "vertical",
+        SplitDirection::Vertical => "horizontal",
+    }
+}
+
+fn size_along(node: &LayoutNode, dir: SplitDirection) -> usize {
+    match dir {
+        SplitDirection::Horizontal => node.width,
+        SplitDirection::Vertical => node.height,
+    }
+}
+
+fn path_value(path: &Path) -> KdlValue {
+    KdlValue::String(path.to_string_lossy().to_string())
+}
+
+// The tab bar and the status bar Zellij shows by default
+fn default_tab_template() -> KdlNode {
+    let bar = |size: usize, plugin: &str| KdlNode::new("pane").with_prop("size", size).with_prop("borderless", true)
+        .with_child(KdlNode::new("plugin").with_prop("location", plugin));
+    KdlNode::new("default_tab_template")
+        .with_child(bar(1, "zellij:tab-bar"))
+        .with_child(KdlNode::new("children"))
+        .with_child(bar(2, "zellij:status-bar"))
+}
+
+/// Converts sessions to Zellij layouts, and Zellij layouts to sessions where the structure allows it
+pub struct ZellijConversion {
+    /// Size of the tmux layouts generated from the sizes of the Zellij panes
+    size: TermSize,
+    /// Settings without any equivalent, left out of the conversion
+    pub unmapped: Vec<String>,
+}
+
+impl ZellijConversion {
+    pub fn new(size: TermSize) -> ZellijConversion {
+        ZellijConversion { size, unmapped: vec![] }
+    }
+
+    /* ---- Session to Zellij layout ---- */
+    // Format:      layout { cwd DIR; tab name=NAME cwd=DIR focus=true { pane split_direction=DIR { pane size="N%" ...; pane } } }
+    pub fn export(&mut self, session: &TmuxSession) -> String {
+        for (key, is_set) in [
+            ("socket_name", session.socket_name.is_some()),
+            ("before_script", session.before_script.is_some()),
+            ("suppress_history", session.suppress_history.is_some()),
+            ("options", !session.options.is_empty()),
+            ("global_options", !session.global_options.is_empty()),
+            ("environment", !session.environment.is_empty()),
+        ].iter() {
+            if *is_set {
+                self.unmapped.push(key.to_string());
+            }
+        }
+
+        let mut layout = KdlNode::new("layout")
+            .with_child(KdlNode::new("cwd").with_arg(path_value(session.start_directory())))
+            .with_child(default_tab_template());
+        for (n, win) in session.windows.iter().enumerate() {
+            let path = key_path("windows", &n.to_string());
+            layout.children.push(self.export_window(&path, win, &session.shell_command_before));
+        }
+
+        let mut content: String = self.unmapped.iter().map(|k| format!("// Not exported: {}\n", k)).collect();
+        content.push_str(&layout.to_string());
+        content
+    }
+
+    fn export_window(&mut self, path: &str, win: &TmuxWindow, session_cmds: &[String]) -> KdlNode {
+        // Zellij never renames its tabs
+        for (name, _) in win.options.iter().chain(win.options_after.iter()).filter(|(n, _)| *n != AUTOMATIC_RENAME) {
+            self.unmapped.push(key_path(&key_path(path, "options"), name));
+        }
+
+        let mut tab = KdlNode::new("tab").with_prop("name", win.window_name.as_str());
+        if win.focus {
+            tab = tab.with_prop("focus", true);
+        }
+        if let Some(dir) = &win.start_directory {
+            tab = tab.with_prop("cwd", path_value(dir));
+        }
+
+        let before: Vec<String> = session_cmds.iter().chain(win.shell_command_before.iter()).cloned().collect();
+        let panes = win.panes.get_panes();
+        // Presets are exported as the splits they make
+        let layout = match win.layout_preset {
+            Some(_) if win.layout.is_none() => win.get_layout(&self.size).ok(),
+            _ => win.layout.clone(),
+        };
+        match &layout {
+            Some(layout) if layout.nb_panes() == panes.len() => {
+                let mut next = 0;
+                tab.children.push(self.export_cell(path, &layout.root, &panes, &mut next, &before));
+            },
+            // Stacked panes
+            _ => for (n, pane) in panes.iter().enumerate() {
+                tab.children.push(self.export_pane(&key_path(path, &n.to_string()), KdlNode::new("pane"), pane, &before));
+            },
+        }
+        tab
+    }
+
+    fn export_cell(&mut self, path: &str, cell: &LayoutNode, panes: &[TmuxPane], next: &mut usize, before: &[String]) -> KdlNode {
+        match &cell.content {
+            LayoutContent::Pane(_) => {
+                *next += 1;
+                self.export_pane(&key_path(path, &(*next - 1).to_string()), KdlNode::new("pane"), &panes[*next - 1], before)
+            },
+            LayoutContent::Split(dir, children) => {
+                let mut split = KdlNode::new("pane").with_prop("split_direction", zellij_direction(*dir));
+                let total: usize = children.iter().map(|c| size_along(c, *dir)).sum();
+                for (n, child) in children.iter().enumerate() {
+                    let mut node = self.export_cell(path, child, panes, next, before);
+                    // The last child gets what is left
+                    if n + 1 < children.len() {
+                        let percent = ((size_along(child, *dir) * 100 + total / 2) / total).max(1);
+                        node.props.insert(0, ("size".to_string(), KdlValue::String(format!("{}%", percent))));
+                    }
+                    split.children.push(node);
+                }
+                split
+            },
+        }
+    }
+
+    fn export_pane(&mut self, path: &str, mut node: KdlNode, pane: &TmuxPane, before: &[String]) -> KdlNode {
+        if pane.focus {
+            node = node.with_prop("focus", true);
+        }
+        if let Some(dir) = &pane.start_directory {
+            node = node.with_prop("cwd", path_value(dir));
+        }
+        for (key, is_set) in [
+            ("sleep_before", pane.sleep_before.is_some()),
+            ("sleep_after", pane.sleep_after.is_some()),
+            ("enter", pane.enter == Some(false)),
+        ].iter() {
+            if *is_set {
+                self.unmapped.push(key_path(path, key));
+            }
+        }
+
+        let cmds: Vec<&String> = before.iter().chain(pane.shell_command.iter()).filter(|c| !c.trim().is_empty()).collect();
+        if cmds.is_empty() {
+            return node;
+        }
+        let words: Vec<&str> = cmds[0].split_whitespace().collect();
+        // A program and its arguments is run as is, anything else needs a shell
+        let (program, args) = if cmds.len() == 1 && words.iter().all(|w| shell_quote(w) == *w) {
+            (words[0].to_string(), words[1..].iter().map(|w| w.to_string()).collect())
+        } else {
+            let script = cmds.iter().map(|c| c.as_str()).collect::<Vec<&str>>().join(CMDS_SEP);
+            (SHELL.to_string(), vec![SHELL_CMD_FLAG.to_string(), script])
+        };
+        node = node.with_prop("command", program.as_str());
+        if !args.is_empty() {
+            let mut args_node = KdlNode::new("args");
+            args_node.args = args.iter().map(|a| KdlValue::from(a.as_str())).collect();
+            node = node.with_child(args_node);
+        }
+        node
+    }
+
+    /* ---- Zellij layout to session ---- */
+    pub fn import(&mut self, content: &str, name: &str) -> Result<TmuxSession, Errcode> {
+        let nodes = parse_kdl(content)?;
+        let layout = nodes.iter().find(|n| n.name == "layout")
+            .ok_or_else(|| Errcode::ParsingError("No layout node in the Zellij layout".to_string()))?;
+
+        let mut root = std::env::current_dir()?;
+        let mut tabs = vec![];
+        let mut loose_panes = vec![];
+        for node in layout.children.iter() {
+            match node.name.as_str() {
+                "cwd" => if let Some(dir) = node.args.first().and_then(|a| a.as_str()) {
+                    root = root.join(dir);
+                },
+                "tab" => tabs.push(node.clone()),
+                "pane" => loose_panes.push(node.clone()),
+                // Only holds the tab bar and the status bar
+                "default_tab_template" => {},
+                _ => self.unmapped.push(node.name.clone()),
+            }
+        }
+        // Panes given without any tab are the content of a single tab
+        if tabs.is_empty() {
+            let mut tab = KdlNode::new("tab");
+            tab.children = loose_panes;
+            tabs.push(tab);
+        } else if !loose_panes.is_empty() {
+            self.unmapped.push("pane".to_string());
+        }
+
+        let mut windows = vec![];
+        for (n, tab) in tabs.iter().enumerate() {
+            windows.push(self.import_tab(&key_path("tab", &n.to_string()), n, tab, &root)?);
+        }
+        let focus = windows.iter().position(|w| w.focus).unwrap_or(0);
+        let mut session = TmuxSession::new(name.to_string(), root, windows);
+        session.set_window_focus(focus)?;
+        Ok(session)
+    }
+
+    fn import_tab(&mut self, path: &str, n: usize, tab: &KdlNode, root: &Path) -> Result<TmuxWindow, Errcode> {
+        let mut win = TmuxWindow::default(root.to_path_buf());
+        win.start_directory = tab.prop("cwd").and_then(|d| d.as_str()).map(|d| root.join(d));
+        // Same default names as Zellij
+        win.window_name = tab.prop("name").and_then(|d| d.as_str()).map(|d| d.to_string())
+            .unwrap_or_else(|| format!("Tab #{}", n + 1));
+        win.set_automatic_rename(false);
+        win.focus = tab.prop("focus") == Some(&KdlValue::Bool(true));
+        for (key, _) in tab.props.iter().filter(|(k, _)| !["name", "cwd", "focus", "split_direction"].contains(&k.as_str())) {
+            self.unmapped.push(key_path(path, key));
+        }
+
+        let mut panes = vec![];
+        let mut focus = 0;
+        let spec = self.import_split(path, tab, &mut panes, &mut focus)?;
+        match spec {
+            Some(spec) if panes.len() > 1 => win.layout = Some(spec.build(&self.size)?),
+            Some(_) => {},
+            None => panes.push(TmuxPane::default()),
+        }
+        win.panes = PaneSerializer::from_panes(panes, focus);
+        Ok(win)
+    }
+
+    // The panes of a tab, or of a pane split in several ones, None if it has no pane tmux can run
+    fn import_split(&mut self, path: &str, node: &KdlNode, panes: &mut Vec<TmuxPane>, focus: &mut usize)
+        -> Result<Option<LayoutSpec>, Errcode>
+    {
+        let dir = match node.prop("split_direction").and_then(|d| d.as_str()) {
+            Some("vertical") => SplitDirection::Horizontal,
+            _ => SplitDirection::Vertical,
+        };
+        let mut cells = vec![];
+        for (n, child) in node.children.iter().enumerate() {
+            let child_path = key_path(path, &n.to_string());
+            match child.name.as_str() {
+                "pane" => cells.extend(self.import_pane(&child_path, child, panes, focus)?),
+                // Where the panes of a template go
+                "children" => {},
+                _ => self.unmapped.push(key_path(path, &child.name)),
+            }
+        }
+        Ok(match cells.len() {
+            0 => None,
+            1 => Some(cells.pop().unwrap().1),
+            _ => Some(LayoutSpec::Split(dir, cells)),
+        })
+    }
+
+    fn import_pane(&mut self, path: &str, node: &KdlNode, panes: &mut Vec<TmuxPane>, focus: &mut usize)
+        -> Result<Option<(CellSize, LayoutSpec)>, Errcode>
+    {
+        let size = match node.prop("size") {
+            None => CellSize::Auto,
+            Some(KdlValue::Number(n)) => CellSize::Cells(usize::from_str(n)?),
+            Some(KdlValue::String(s)) => match s.strip_suffix('%') {
+                Some(p) => CellSize::Percent(usize::from_str(p)?),
+                None => CellSize::Cells(usize::from_str(s)?),
+            },
+            Some(s) => return Err(Errcode::ParsingError(format!("{}: invalid size {}", path, s))),
+        };
+        if node.prop("plugin").is_some() || node.child("plugin").is_some() {
+            self.unmapped.push(key_path(path, "plugin"));
+            return Ok(None);
+        }
+        if node.child("pane").is_some() {
+            return Ok(self.import_split(path, node, panes, focus)?.map(|spec| (size, spec)));
+        }
+
+        let mut pane = TmuxPane::default();
+        for (key, val) in node.props.iter() {
+            match (key.as_str(), val) {
+                ("size", _) | ("split_direction", _) => {},
+                ("cwd", KdlValue::String(dir)) => pane.start_directory = Some(PathBuf::from(dir)),
+                ("focus", KdlValue::Bool(true)) => *focus = panes.len(),
+                ("focus", KdlValue::Bool(false)) => {},
+                ("command", KdlValue::String(program)) => {
+                    let args: Vec<String> = node.child("args").map(|a| a.args.iter()
+                        .map(|a| match a {
+                            KdlValue::String(s) => s.clone(),
+                            _ => a.to_string(),
+                        }).collect()).unwrap_or_default();
+                    pane.shell_command = vec![pane_command(program, &args)];
+                },
+                _ => self.unmapped.push(key_path(path, key)),
+            }
+        }
+        for child in node.children.iter().filter(|c| c.name != "args") {
+            self.unmapped.push(key_path(path, &child.name));
+        }
+        panes.push(pane);
+        Ok(Some((size, LayoutSpec::Pane)))
+    }
+}
+
+// The commands of shells started with "-c" are written as they were
+fn pane_command(program: &str, args: &[String]) -> String {
+    match args {
+        [flag, script] if flag == SHELL_CMD_FLAG && SHELLS.contains(&program) => script.clone(),
+        _ => std::iter::once(program).chain(args.iter().map(|a| a.as_str()))
+            .map(shell_quote).collect::<Vec<String>>().join(" "),
+    }
+}
+
+
+
+
+
+#[test]
+fn test_zellij_conversion(){
+    use crate::serialisation::FileFormat;
+
+    let session = TmuxSession::from_str(r#"{
+        "session_name": "api",
+        "start_directory": "/srv/api",
+        "windows": [
+            {
+                "window_name": "code",
+                "layout": "7d3a,100x30,0,0{60x30,0,0,1,39x30,61,0[39x15,61,0,2,39x14,61,16,3]}",
+                "start_directory": "src",
+                "panes": [
+                    "nvim src/main.rs",
+                    {"shell_command": ["cargo build", "cargo test -- --nocapture"], "focus": "true"},
+                    {"shell_command": " ", "start_directory": "../logs", "sleep_before": 1}
+                ],
+                "options": {"automatic-rename": "off", "synchronize-panes": "on"}
+            },
+            {"window_name": "shell", "focus": "true", "panes": ["htop", "tail -f 'app log'"]}
+        ],
+        "socket_name": "work"
+    }"#, FileFormat::Json).unwrap();
+
+    let mut conversion = ZellijConversion::new(TermSize { width: 100, height: 30 });
+    let kdl = conversion.export(&session);
+    assert_eq!(conversion.unmapped, vec!["socket_name", "windows.0.options.synchronize-panes", "windows.0.2.sleep_before"]);
+    assert!(kdl.starts_with("// Not exported: socket_name\n"));
+    assert!(kdl.contains("    cwd \"/srv/api\"\n"));
+    assert!(kdl.contains("    tab name=\"code\" cwd=\"src\" {\n        pane split_direction=\"vertical\" {\n            pane size=\"61%\" command=\"nvim\" {\n"));
+    assert!(kdl.contains("pane size=\"52%\" focus=true command=\"sh\" {\n                    args \"-c\" \"cargo build; cargo test -- --nocapture\"\n"));
+    assert!(kdl.contains("pane cwd=\"../logs\"\n"));
+    // Without a directory of its own, a tab starts in the one of the layout
+    assert!(kdl.contains("tab name=\"shell\" focus=true {\n        pane focus=true command=\"htop\"\n"));
+
+    let mut conversion = ZellijConversion::new(TermSize { width: 100, height: 30 });
+    let back = conversion.import(&kdl, "api").unwrap();
+    assert!(conversion.unmapped.is_empty(), "{:?}", conversion.unmapped);
+    assert_eq!(back.start_directory(), Path::new("/srv/api"));
+    assert_eq!(back.windows.len(), 2);
+    let code = &back.windows[0];
+    assert_eq!(code.start_directory, Some(PathBuf::from("/srv/api/src")));
+    assert_eq!(code.panes.get_panes_cmds().unwrap(), vec!["nvim src/main.rs", "cargo build; cargo test -- --nocapture", ""]);
+    assert_eq!(code.panes.get_panes()[2].start_directory, Some(PathBuf::from("../logs")));
+    assert_eq!(code.panes.focused_index(), 1);
+    // Same geometry, the panes are numbered from 0
+    assert!(code.layout.as_ref().unwrap().to_string().ends_with(",100x30,0,0{60x30,0,0,0,39x30,61,0[39x15,61,0,1,39x14,61,16,2]}"));
+    assert!(back.windows[1].focus && !code.focus);
+    assert_eq!(back.windows[1].start_directory, None);
+    // Stacked panes
+    assert_eq!(back.windows[1].layout.as_ref().unwrap().to_string(), "c08a,100x30,0,0[100x15,0,0,0,100x14,0,16,1]");
+    assert_eq!(back.windows[1].panes.get_panes_cmds().unwrap(), vec!["htop", "tail -f 'app log'"]);
+
+    let mut conversion = ZellijConversion::new(TermSize { width: 100, height: 30 });
+    let imported = conversion.import(r#"
+layout {
+    pane size=1 borderless=true { plugin location="zellij:tab-bar"; }
+    pane name="editor" edit="src/main.rs"
+    pane command="bash" { args "-c" "make run"; }
+    floating_panes { pane; }
+}"#, "loose").unwrap();
+    assert_eq!(conversion.unmapped, vec!["floating_panes", "tab.0.0.plugin", "tab.0.1.name", "tab.0.1.edit"]);
+    assert_eq!(imported.windows[0].window_name, "Tab #1");
+    assert_eq!(imported.windows[0].panes.get_panes_cmds().unwrap(), vec!["", "make run"]);
+    assert!(conversion.import("tab { pane; }", "x").is_err());
+}
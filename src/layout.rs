@@ -0,0 +1,824 @@
+use std::fmt;
+use std::env;
+use std::str::FromStr;
+use std::process::{Command, Stdio};
+
+use nom::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::take_while_m_n;
+use nom::character::complete::{char, digit1};
+use nom::combinator::{all_consuming, map, map_res, not, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded, terminated, tuple};
+
+use crate::errors::Errcode;
+
+pub mod dsl;
+pub mod preset;
+
+use dsl::parse_split_dsl;
+use preset::LayoutPreset;
+
+/// How the children of a split cell are arranged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitDirection {
+    /// Children side by side, from left to right (`{...}` in tmux layouts)
+    Horizontal,
+    /// Children stacked, from top to bottom (`[...]` in tmux layouts)
+    Vertical,
+}
+
+impl FromStr for SplitDirection {
+    type Err = Errcode;
+
+    // Format:      h (side by side) | v (stacked)
+    fn from_str(s: &str) -> Result<SplitDirection, Errcode> {
+        match s {
+            "h" => Ok(SplitDirection::Horizontal),
+            "v" => Ok(SplitDirection::Vertical),
+            _ => Err(Errcode::ArgValidationError("split direction, expected \"h\" or \"v\"")),
+        }
+    }
+}
+
+impl SplitDirection {
+    pub fn other(&self) -> SplitDirection {
+        match self {
+            SplitDirection::Horizontal => SplitDirection::Vertical,
+            SplitDirection::Vertical => SplitDirection::Horizontal,
+        }
+    }
+
+    fn delimiters(&self) -> (char, char) {
+        match self {
+            SplitDirection::Horizontal => ('{', '}'),
+            SplitDirection::Vertical => ('[', ']'),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutContent {
+    /// A pane, with the tmux pane id if the layout contains it
+    Pane(Option<usize>),
+    Split(SplitDirection, Vec<LayoutNode>),
+}
+
+/// A cell of a tmux layout, sizes and offsets are expressed in terminal cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutNode {
+    pub width: usize,
+    pub height: usize,
+    pub x: usize,
+    pub y: usize,
+    pub content: LayoutContent,
+}
+
+// Same as tmux, the smallest size a pane can have in each direction
+const PANE_MINIMUM: usize = 1;
+
+impl LayoutNode {
+    pub fn nb_panes(&self) -> usize {
+        match &self.content {
+            LayoutContent::Pane(_) => 1,
+            LayoutContent::Split(_, children) => children.iter().map(|c| c.nb_panes()).sum(),
+        }
+    }
+
+    fn size_along(&self, direction: SplitDirection) -> usize {
+        match direction {
+            SplitDirection::Horizontal => self.width,
+            SplitDirection::Vertical => self.height,
+        }
+    }
+
+    fn size_along_mut(&mut self, direction: SplitDirection) -> &mut usize {
+        match direction {
+            SplitDirection::Horizontal => &mut self.width,
+            SplitDirection::Vertical => &mut self.height,
+        }
+    }
+
+    fn max_pane_id(&self) -> Option<usize> {
+        match &self.content {
+            LayoutContent::Pane(id) => *id,
+            LayoutContent::Split(_, children) => children.iter().filter_map(|c| c.max_pane_id()).max(),
+        }
+    }
+
+    /// Calls f on the split containing the pane (in tmux order), with the index of the pane in the split
+    /// Returns None if the pane is not found in this cell
+    fn edit_pane_parent<F>(&mut self, pane: &mut usize, f: &mut F) -> Option<Result<(), Errcode>>
+    where F: FnMut(&mut LayoutNode, usize) -> Result<(), Errcode>
+    {
+        let nchildren = match &self.content {
+            LayoutContent::Split(_, children) => children.len(),
+            LayoutContent::Pane(_) => return None,
+        };
+
+        for i in 0..nchildren {
+            let found = match &mut self.content {
+                LayoutContent::Split(_, children) if !matches!(children[i].content, LayoutContent::Pane(_)) => {
+                    if let Some(res) = children[i].edit_pane_parent(pane, f) {
+                        return Some(res);
+                    }
+                    false
+                },
+                _ if *pane == 0 => true,
+                _ => {
+                    *pane -= 1;
+                    false
+                },
+            };
+            if found {
+                return Some(f(self, i));
+            }
+        }
+        None
+    }
+
+    /// Smallest size the cell can be shrinked to in the given direction
+    pub fn min_size(&self, direction: SplitDirection) -> usize {
+        match &self.content {
+            LayoutContent::Pane(_) => PANE_MINIMUM,
+            LayoutContent::Split(dir, children) => {
+                let mins = children.iter().map(|c| c.min_size(direction));
+                if *dir == direction {
+                    mins.sum::<usize>() + children.len() - 1
+                } else {
+                    mins.max().unwrap_or(PANE_MINIMUM)
+                }
+            },
+        }
+    }
+
+    /// Checks that the children of every split fill their parent exactly, as tmux does
+    pub fn is_consistent(&self) -> bool {
+        match &self.content {
+            LayoutContent::Pane(_) => true,
+            LayoutContent::Split(dir, children) => {
+                let other = dir.other();
+                let total: usize = children.iter().map(|c| c.size_along(*dir)).sum();
+                total + children.len() - 1 == self.size_along(*dir)
+                    && children.iter().all(|c| c.size_along(other) == self.size_along(other) && c.is_consistent())
+            },
+        }
+    }
+
+    /// Proportionally resizes the cell and all its children, and moves it to (x, y)
+    pub fn resize(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<(), Errcode> {
+        let (min_width, min_height) = (self.min_size(SplitDirection::Horizontal), self.min_size(SplitDirection::Vertical));
+        if width < min_width || height < min_height {
+            return Err(Errcode::LayoutError(
+                format!("Cannot fit the layout in {}x{}, needs at least {}x{}", width, height, min_width, min_height)
+            ));
+        }
+
+        if let LayoutContent::Split(dir, children) = &mut self.content {
+            let new_total = match dir {
+                SplitDirection::Horizontal => width,
+                SplitDirection::Vertical => height,
+            } - (children.len() - 1);
+            let old_sizes: Vec<usize> = children.iter().map(|c| c.size_along(*dir)).collect();
+            let mins: Vec<usize> = children.iter().map(|c| c.min_size(*dir)).collect();
+
+            let mut offset = 0;
+            for (child, size) in children.iter_mut().zip(scale_sizes(&old_sizes, &mins, new_total)) {
+                match dir {
+                    SplitDirection::Horizontal => child.resize(x + offset, y, size, height)?,
+                    SplitDirection::Vertical => child.resize(x, y + offset, width, size)?,
+                }
+                offset += size + 1;
+            }
+        }
+
+        self.x = x;
+        self.y = y;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+}
+
+// Replaces a pane by a split of itself and a new pane, the new pane using `percent` of the space
+fn split_cell(cell: &LayoutNode, direction: SplitDirection, percent: usize, new_id: Option<usize>) -> Result<LayoutNode, Errcode> {
+    let total = cell.size_along(direction);
+    if total < 2 * PANE_MINIMUM + 1 {
+        return Err(Errcode::LayoutError(format!("Pane too small to be split ({} cells)", total)));
+    }
+    let available = total - 1;
+    let new_size = (available * percent / 100).max(PANE_MINIMUM).min(available - PANE_MINIMUM);
+
+    let mut old = cell.clone();
+    *old.size_along_mut(direction) = available - new_size;
+    let mut new = LayoutNode { content: LayoutContent::Pane(new_id), ..cell.clone() };
+    *new.size_along_mut(direction) = new_size;
+
+    Ok(LayoutNode {
+        content: LayoutContent::Split(direction, vec![old, new]),
+        ..cell.clone()
+    })
+}
+
+// Scales the sizes so they sum up to total, keeping the ratios between them and the minimum sizes
+fn scale_sizes(sizes: &[usize], mins: &[usize], total: usize) -> Vec<usize> {
+    let old_total = sizes.iter().sum::<usize>().max(1);
+
+    // Round down, then give the cells left to the sizes with the biggest rounding error
+    let mut res: Vec<usize> = sizes.iter().map(|s| s * total / old_total).collect();
+    let mut by_remainder: Vec<usize> = (0..sizes.len()).collect();
+    by_remainder.sort_by_key(|i| std::cmp::Reverse((sizes[*i] * total) % old_total));
+    let left = total - res.iter().sum::<usize>();
+    for i in by_remainder.iter().cycle().take(left) {
+        res[*i] += 1;
+    }
+
+    // Grow the cells under their minimum size, taking space from the ones with the most margin
+    for i in 0..res.len() {
+        while res[i] < mins[i] {
+            let biggest = (0..res.len())
+                .max_by_key(|j| res[*j] as isize - mins[*j] as isize)
+                .unwrap();
+            res[biggest] -= 1;
+            res[i] += 1;
+        }
+    }
+    res
+}
+
+impl fmt::Display for LayoutNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{},{},{}", self.width, self.height, self.x, self.y)?;
+        match &self.content {
+            LayoutContent::Pane(Some(id)) => write!(f, ",{}", id),
+            LayoutContent::Pane(None) => Ok(()),
+            LayoutContent::Split(dir, children) => {
+                let (open, close) = dir.delimiters();
+                write!(f, "{}", open)?;
+                for (n, child) in children.iter().enumerate() {
+                    if n > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", child)?;
+                }
+                write!(f, "{}", close)
+            }
+        }
+    }
+}
+
+/// A complete tmux layout string, as given by `#{window_layout}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmuxLayout {
+    pub checksum: u16,
+    pub root: LayoutNode,
+}
+
+impl TmuxLayout {
+    pub fn new(root: LayoutNode) -> TmuxLayout {
+        let mut layout = TmuxLayout { checksum: 0, root };
+        layout.update_checksum();
+        layout
+    }
+
+    pub fn nb_panes(&self) -> usize {
+        self.root.nb_panes()
+    }
+
+    pub fn size(&self) -> TermSize {
+        TermSize { width: self.root.width, height: self.root.height }
+    }
+
+    /// Rescales the whole layout to a new window size, keeping the ratios of the splits
+    pub fn resize(&mut self, size: &TermSize) -> Result<(), Errcode> {
+        self.root.resize(0, 0, size.width, size.height)?;
+        self.update_checksum();
+        Ok(())
+    }
+
+    // Recomputes the offsets of all the cells after their sizes have been changed
+    fn refresh(&mut self) -> Result<(), Errcode> {
+        let size = self.size();
+        self.resize(&size)
+    }
+
+    fn no_split_error(pane: usize) -> Errcode {
+        Errcode::LayoutError(format!("Pane {} is not part of any split", pane))
+    }
+
+    fn pane_not_found_error(&self, pane: usize) -> Errcode {
+        Errcode::LayoutError(format!("No pane {} in a layout of {} panes", pane, self.nb_panes()))
+    }
+
+    /// Splits the pane in two, the new pane is placed after it and uses `percent` of its space
+    pub fn split_pane(&mut self, pane: usize, direction: SplitDirection, percent: usize) -> Result<(), Errcode> {
+        if pane >= self.nb_panes() {
+            return Err(self.pane_not_found_error(pane));
+        }
+        let new_id = self.root.max_pane_id().map(|id| id + 1);
+
+        if let LayoutContent::Pane(_) = self.root.content {
+            self.root = split_cell(&self.root, direction, percent, new_id)?;
+        } else {
+            let mut index = pane;
+            self.root.edit_pane_parent(&mut index, &mut |parent, i| {
+                if let LayoutContent::Split(dir, children) = &mut parent.content {
+                    let split = split_cell(&children[i], direction, percent, new_id)?;
+                    match split.content {
+                        // Same as tmux, splitting in the direction of the parent adds a cell to it
+                        LayoutContent::Split(_, new_children) if *dir == direction => {
+                            children.splice(i..=i, new_children);
+                        },
+                        _ => children[i] = split,
+                    }
+                }
+                Ok(())
+            }).unwrap()?;
+        }
+        self.refresh()
+    }
+
+    /// Removes the pane, its space is given to the previous cell of the split (or the next one if first)
+    pub fn close_pane(&mut self, pane: usize) -> Result<(), Errcode> {
+        if pane >= self.nb_panes() {
+            return Err(self.pane_not_found_error(pane));
+        }
+        let mut index = pane;
+        self.root.edit_pane_parent(&mut index, &mut |parent, i| {
+            if let LayoutContent::Split(dir, children) = &mut parent.content {
+                let removed = children.remove(i);
+                let neighbour = if i > 0 { i - 1 } else { 0 };
+                *children[neighbour].size_along_mut(*dir) += removed.size_along(*dir) + 1;
+
+                if children.len() == 1 {
+                    let last = children.pop().unwrap();
+                    parent.content = last.content;
+                }
+            }
+            Ok(())
+        }).ok_or_else(|| Self::no_split_error(pane))??;
+        self.refresh()
+    }
+
+    /// Turns the split containing the pane by 90 degrees, keeping the ratios between its cells
+    pub fn rotate_split(&mut self, pane: usize) -> Result<(), Errcode> {
+        if pane >= self.nb_panes() {
+            return Err(self.pane_not_found_error(pane));
+        }
+        let mut index = pane;
+        self.root.edit_pane_parent(&mut index, &mut |parent, _| {
+            if let LayoutContent::Split(dir, children) = &mut parent.content {
+                // The sizes of the cells are used as weights when the split is resized
+                for child in children.iter_mut() {
+                    let size = child.size_along(*dir);
+                    *child.size_along_mut(dir.other()) = size;
+                }
+                *dir = dir.other();
+            }
+            Ok(())
+        }).ok_or_else(|| Self::no_split_error(pane))??;
+        self.refresh()
+    }
+
+    pub fn compute_checksum(&self) -> u16 {
+        layout_checksum_of(&self.root.to_string())
+    }
+
+    pub fn is_checksum_valid(&self) -> bool {
+        self.checksum == self.compute_checksum()
+    }
+
+    /// Has to be called after any modification of the layout tree
+    pub fn update_checksum(&mut self) {
+        self.checksum = self.compute_checksum();
+    }
+}
+
+/// Checksum of a layout body (everything after the "CSUM," prefix), as computed by tmux
+pub fn layout_checksum_of(body: &str) -> u16 {
+    body.bytes().fold(0u16, |csum, c| {
+        csum.rotate_right(1).wrapping_add(c as u16)
+    })
+}
+
+impl FromStr for TmuxLayout {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<TmuxLayout, Errcode> {
+        match all_consuming(tuple((layout_checksum, char(','), layout_node)))(s) {
+            Ok((_, (_, _, root))) if !root.is_consistent() => Err(Errcode::ParsingError(
+                format!("Invalid tmux layout \"{}\": cells sizes do not match", s)
+            )),
+            Ok((_, (checksum, _, root))) => Ok(TmuxLayout { checksum, root }),
+            Err(e) => Err(Errcode::ParsingError(
+                format!("Invalid tmux layout \"{}\": {}", s, Errcode::from(e).details())
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TmuxLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x},{}", self.checksum, self.root)
+    }
+}
+
+/* ------------- Layout generation ------------- */
+/// Size of a terminal (or of a tmux window), in cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TermSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+const DEFAULT_TERM_SIZE: TermSize = TermSize { width: 80, height: 24 };
+
+impl TermSize {
+    /// Size of the terminal we are running in, falls back on 80x24 if it cannot be found
+    pub fn current() -> TermSize {
+        // "stty size" reads the size of the terminal attached to stdin, prints "ROWS COLS"
+        let stty = Command::new("stty").arg("size")
+            .stdin(Stdio::inherit()).stderr(Stdio::null())
+            .output().ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| String::from_utf8(out.stdout).ok())
+            .and_then(|out| {
+                let mut it = out.split_whitespace().filter_map(|n| usize::from_str(n).ok());
+                let (height, width) = (it.next()?, it.next()?);
+                Some(TermSize { width, height })
+            });
+
+        stty.or_else(|| {
+            let width = usize::from_str(&env::var("COLUMNS").ok()?).ok()?;
+            let height = usize::from_str(&env::var("LINES").ok()?).ok()?;
+            Some(TermSize { width, height })
+        })
+        .filter(|size| size.width > 0 && size.height > 0)
+        .unwrap_or(DEFAULT_TERM_SIZE)
+    }
+}
+
+impl FromStr for TermSize {
+    type Err = Errcode;
+
+    // Format:      WIDTHxHEIGHT
+    fn from_str(s: &str) -> Result<TermSize, Errcode> {
+        let invalid = || Errcode::ArgValidationError("terminal size, expected WIDTHxHEIGHT");
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let size = TermSize {
+            width: usize::from_str(width).map_err(|_| invalid())?,
+            height: usize::from_str(height).map_err(|_| invalid())?,
+        };
+        if size.width == 0 || size.height == 0 {
+            return Err(invalid());
+        }
+        Ok(size)
+    }
+}
+
+impl fmt::Display for TermSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// The size requested for a child of a split
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellSize {
+    /// Shares the space left by the other children
+    Auto,
+    /// Percentage of the space available in the split
+    Percent(usize),
+    /// Fixed number of cells
+    Cells(usize),
+}
+
+/// Description of a layout, without any actual size, used to generate a LayoutNode tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutSpec {
+    Pane,
+    Split(SplitDirection, Vec<(CellSize, LayoutSpec)>),
+}
+
+impl LayoutSpec {
+    /// A split of n panes of the same size
+    pub fn even(direction: SplitDirection, npanes: usize) -> LayoutSpec {
+        if npanes <= 1 {
+            LayoutSpec::Pane
+        } else {
+            LayoutSpec::Split(direction, (0..npanes).map(|_| (CellSize::Auto, LayoutSpec::Pane)).collect())
+        }
+    }
+
+    pub fn build(&self, size: &TermSize) -> Result<TmuxLayout, Errcode> {
+        let mut next_id = 0;
+        let root = self.build_node(0, 0, size.width, size.height, &mut next_id)?;
+        Ok(TmuxLayout::new(root))
+    }
+
+    fn build_node(&self, x: usize, y: usize, width: usize, height: usize, next_id: &mut usize)
+        -> Result<LayoutNode, Errcode>
+    {
+        let content = match self {
+            LayoutSpec::Pane => {
+                *next_id += 1;
+                LayoutContent::Pane(Some(*next_id - 1))
+            },
+            LayoutSpec::Split(_, children) if children.len() == 1 => {
+                return children[0].1.build_node(x, y, width, height, next_id);
+            },
+            LayoutSpec::Split(dir, children) => {
+                let total = match dir {
+                    SplitDirection::Horizontal => width,
+                    SplitDirection::Vertical => height,
+                };
+                let sizes = split_sizes(total, &children.iter().map(|(s, _)| *s).collect::<Vec<CellSize>>())?;
+
+                let mut offset = 0;
+                let mut nodes = vec![];
+                for ((_, child), size) in children.iter().zip(sizes) {
+                    nodes.push(match dir {
+                        SplitDirection::Horizontal => child.build_node(x + offset, y, size, height, next_id)?,
+                        SplitDirection::Vertical => child.build_node(x, y + offset, width, size, next_id)?,
+                    });
+                    offset += size + 1;
+                }
+                LayoutContent::Split(*dir, nodes)
+            },
+        };
+        Ok(LayoutNode { width, height, x, y, content })
+    }
+}
+
+/// Splits `total` cells between children, keeping a 1 cell border between each of them
+pub fn split_sizes(total: usize, sizes: &[CellSize]) -> Result<Vec<usize>, Errcode> {
+    let n = sizes.len();
+    if total + 1 < 2 * n {
+        return Err(Errcode::LayoutError(format!("Cannot fit {} panes in {} cells", n, total)));
+    }
+    let available = total - (n - 1);
+
+    let percents: usize = sizes.iter().map(|s| if let CellSize::Percent(p) = s { *p } else { 0 }).sum();
+    if percents > 100 {
+        return Err(Errcode::LayoutError(format!("Split percentages sum up to {}%", percents)));
+    }
+
+    let mut res: Vec<usize> = sizes.iter().map(|s| match s {
+        CellSize::Auto => 0,
+        CellSize::Percent(p) => (available * p / 100).max(1),
+        CellSize::Cells(c) => (*c).max(1),
+    }).collect();
+
+    let used: usize = res.iter().sum();
+    if used + sizes.iter().filter(|s| **s == CellSize::Auto).count() > available {
+        return Err(Errcode::LayoutError(format!("Requested sizes do not fit in {} cells", available)));
+    }
+    let mut left = available - used;
+
+    let nauto = sizes.iter().filter(|s| **s == CellSize::Auto).count();
+    match left.checked_div(nauto) {
+        // Give whatever is left to the last child
+        None => *res.last_mut().unwrap() += left,
+
+        // Same as tmux, the first children get the remainder
+        Some(each) => {
+            let mut remainder = left % nauto;
+            for (r, s) in res.iter_mut().zip(sizes) {
+                if *s == CellSize::Auto {
+                    *r = each + if remainder > 0 { 1 } else { 0 };
+                    remainder = remainder.saturating_sub(1);
+                    left -= *r;
+                }
+            }
+            assert_eq!(left, 0);
+        },
+    }
+    Ok(res)
+}
+
+/// A way to generate a layout: a tmux preset, or a split described with the DSL
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutTemplate {
+    Preset(LayoutPreset),
+    Split(LayoutSpec),
+}
+
+impl LayoutTemplate {
+    /// Generates the layout, presets are generated with `npanes` panes
+    pub fn generate(&self, size: &TermSize, npanes: usize) -> Result<TmuxLayout, Errcode> {
+        match self {
+            LayoutTemplate::Preset(p) => p.to_spec(size, npanes).build(size),
+            LayoutTemplate::Split(s) => s.build(size),
+        }
+    }
+}
+
+impl FromStr for LayoutTemplate {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<LayoutTemplate, Errcode> {
+        match LayoutPreset::from_str(s) {
+            Ok(p) => Ok(LayoutTemplate::Preset(p)),
+            Err(_) => Ok(LayoutTemplate::Split(parse_split_dsl(s)?)),
+        }
+    }
+}
+
+/* ------------- Layout string parsing ------------- */
+// Format:      CSUM,WxH,X,Y[,PANE_ID]      or      CSUM,WxH,X,Y{CELL,CELL,...}    (or [...])
+
+fn layout_checksum(input: &str) -> IResult<&str, u16> {
+    map_res(
+        take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+        |s| u16::from_str_radix(s, 16)
+    )(input)
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, usize::from_str)(input)
+}
+
+// The pane id is optional, a number followed by an "x" is the size of the next cell
+fn pane_id(input: &str) -> IResult<&str, usize> {
+    preceded(char(','), terminated(number, not(char('x'))))(input)
+}
+
+fn layout_children(open: char, close: char) -> impl FnMut(&str) -> IResult<&str, Vec<LayoutNode>> {
+    move |input| delimited(char(open), separated_list1(char(','), layout_node), char(close))(input)
+}
+
+fn layout_node(input: &str) -> IResult<&str, LayoutNode> {
+    let (input, (width, _, height, _, x, _, y)) = tuple(
+        (number, char('x'), number, char(','), number, char(','), number)
+    )(input)?;
+
+    let (input, content) = alt((
+        map(layout_children('{', '}'), |c| LayoutContent::Split(SplitDirection::Horizontal, c)),
+        map(layout_children('[', ']'), |c| LayoutContent::Split(SplitDirection::Vertical, c)),
+        map(opt(pane_id), LayoutContent::Pane),
+    ))(input)?;
+
+    Ok((input, LayoutNode { width, height, x, y, content }))
+}
+
+
+
+
+
+#[test]
+fn test_layout_nb_panes(){
+    let test_points = [
+        ("5be4,211x62,0,0,15", 1),
+        ("f93e,211x62,0,0[211x31,0,0,15,211x30,0,32,24]", 2),
+        ("6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}", 2),
+        ("dcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}", 3),
+        ("7303,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x30,106,32,27]}", 4),
+        ("1bd3,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x15,106,32,27,105x14,106,48{52x14,106,48,28,26x14,159,48,29,25x14,186,48[25x7,186,48,30,25x6,186,56,31]}]}", 8)
+    ];
+
+    for (ntest, (layout, exp)) in test_points.iter().enumerate(){
+        let got = TmuxLayout::from_str(layout).expect("Layout parsing raised error");
+        println!("Layout {} expect {} panes, got {}", ntest, exp, got.nb_panes());
+        assert_eq!(got.nb_panes(), *exp);
+        assert_eq!(got.to_string(), *layout);
+    }
+}
+
+#[test]
+fn test_layout_tree(){
+    let layout = TmuxLayout::from_str("dcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}").unwrap();
+    assert_eq!(layout.checksum, 0xdcbe);
+    if let LayoutContent::Split(SplitDirection::Horizontal, children) = &layout.root.content {
+        assert_eq!(children.len(), 2);
+        assert!(matches!(children[0].content, LayoutContent::Split(SplitDirection::Vertical, _)));
+        assert_eq!(children[1].content, LayoutContent::Pane(Some(25)));
+        assert_eq!((children[1].width, children[1].height, children[1].x, children[1].y), (105, 62, 106, 0));
+    } else {
+        panic!("Root of the layout should be an horizontal split");
+    }
+
+    // Pane ids are optional
+    let layout = TmuxLayout::from_str("0000,211x62,0,0{105x62,0,0,105x62,106,0}").unwrap();
+    assert_eq!(layout.nb_panes(), 2);
+    assert_eq!(layout.to_string(), "0000,211x62,0,0{105x62,0,0,105x62,106,0}");
+}
+
+#[test]
+fn test_layout_checksum(){
+    let test_points = [
+        "5be4,211x62,0,0,15",
+        "f93e,211x62,0,0[211x31,0,0,15,211x30,0,32,24]",
+        "6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}",
+        "1bd3,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x15,106,32,27,105x14,106,48{52x14,106,48,28,26x14,159,48,29,25x14,186,48[25x7,186,48,30,25x6,186,56,31]}]}",
+        "ff43,211x62,0,0[211x31,0,0{105x31,0,0,19,105x31,106,0,22},211x30,0,32{105x30,0,32,20,105x30,106,32,21}]",
+    ];
+
+    for layout in test_points.iter(){
+        let got = TmuxLayout::from_str(layout).unwrap();
+        assert!(got.is_checksum_valid(), "{}", layout);
+        assert_eq!(TmuxLayout::new(got.root.clone()), got);
+    }
+
+    let mut edited = TmuxLayout::from_str("5be4,211x62,0,0{105x62,0,0,15,105x62,106,0,25}").unwrap();
+    assert!(!edited.is_checksum_valid());
+    edited.update_checksum();
+    assert_eq!(edited.to_string(), "6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}");
+}
+
+#[test]
+fn test_layout_generation(){
+    let size = TermSize::from_str("211x62").unwrap();
+    let test_points = [
+        ("even-horizontal", 2, "211x62,0,0{105x62,0,0,0,105x62,106,0,1}"),
+        ("even-vertical", 1, "211x62,0,0,0"),
+        ("even-vertical", 3, "211x62,0,0[211x20,0,0,0,211x20,0,21,1,211x20,0,42,2]"),
+        ("h(v(a,b),c)", 3, "211x62,0,0{105x62,0,0[105x31,0,0,0,105x30,0,32,1],105x62,106,0,2}"),
+        ("v(50%:a,b)", 0, "211x62,0,0[211x30,0,0,0,211x31,0,31,1]"),
+    ];
+
+    for (template, npanes, exp) in test_points.iter(){
+        let got = LayoutTemplate::from_str(template).unwrap().generate(&size, *npanes).unwrap();
+        assert_eq!(got.root.to_string(), *exp, "{}", template);
+        assert!(got.is_checksum_valid());
+    }
+
+    assert!(LayoutTemplate::from_str("h(a,b)").unwrap().generate(&TermSize::from_str("2x10").unwrap(), 2).is_err());
+    assert!(LayoutTemplate::from_str("h(70%:a,40%:b)").unwrap().generate(&size, 2).is_err());
+}
+
+#[test]
+fn test_layout_resize(){
+    let mut layout = TmuxLayout::from_str("1bd3,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x15,106,32,27,105x14,106,48{52x14,106,48,28,26x14,159,48,29,25x14,186,48[25x7,186,48,30,25x6,186,56,31]}]}").unwrap();
+    let orig = layout.clone();
+
+    for (width, height) in [(80, 24), (400, 100), (211, 62), (17, 13)].iter() {
+        let size = TermSize { width: *width, height: *height };
+        layout.resize(&size).unwrap();
+        assert_eq!(layout.size(), size);
+        assert!(layout.root.is_consistent());
+        assert!(layout.is_checksum_valid());
+        assert_eq!(layout.nb_panes(), 8);
+    }
+
+    layout.resize(&orig.size()).unwrap();
+    assert_eq!(layout.nb_panes(), orig.nb_panes());
+
+    let mut layout = TmuxLayout::from_str("6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}").unwrap();
+    layout.resize(&TermSize { width: 101, height: 30 }).unwrap();
+    assert_eq!(layout.root.to_string(), "101x30,0,0{50x30,0,0,15,50x30,51,0,25}");
+
+    // Smallest size is 1 cell per pane and 1 cell per border
+    assert!(layout.resize(&TermSize { width: 3, height: 1 }).is_ok());
+    assert!(layout.resize(&TermSize { width: 2, height: 1 }).is_err());
+}
+
+#[test]
+fn test_layout_edition(){
+    let mut layout = TmuxLayout::from_str("6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}").unwrap();
+
+    layout.split_pane(0, SplitDirection::Vertical, 50).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}");
+
+    // Same direction as the parent split, the pane is added to it
+    layout.split_pane(2, SplitDirection::Horizontal, 50).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],52x62,106,0,25,52x62,159,0,27}");
+    assert!(layout.is_checksum_valid());
+
+    layout.close_pane(3).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}");
+
+    layout.close_pane(0).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{105x62,0,0,26,105x62,106,0,25}");
+
+    layout.rotate_split(1).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0[211x31,0,0,26,211x30,0,32,25]");
+
+    layout.close_pane(1).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0,26");
+    assert!(layout.close_pane(0).is_err());
+    assert!(layout.rotate_split(0).is_err());
+    assert!(layout.split_pane(1, SplitDirection::Vertical, 50).is_err());
+
+    layout.split_pane(0, SplitDirection::Horizontal, 30).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{147x62,0,0,26,63x62,148,0,27}");
+    assert!(layout.root.is_consistent());
+}
+
+#[test]
+fn test_layout_parsing_errors(){
+    let test_points = [
+        "",
+        "211x62,0,0,15",
+        "5be4,211x62,0,0,15,",
+        "5be4,211x62,0{105x62,0,0,15}",
+        "f93e,211x62,0,0[211x31,0,0,15,211x30,0,32,24",
+        "f93e,211x62,0,0[]",
+        "zzzz,211x62,0,0,15",
+        "f93e,211x62,0,0[211x31,0,0,15,211x31,0,32,24]",
+        "6669,211x62,0,0{105x62,0,0,15,105x61,106,0,25}",
+    ];
+
+    for layout in test_points.iter(){
+        assert!(matches!(TmuxLayout::from_str(layout), Err(Errcode::ParsingError(_))), "{}", layout);
+    }
+}
@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+use nom::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::{char, digit1, one_of};
+use nom::combinator::{all_consuming, map, map_res, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, terminated};
+
+use crate::errors::Errcode;
+use crate::layout::{CellSize, LayoutSpec, SplitDirection};
+
+/* ------------- Split DSL ------------- */
+// Format:      NODE := NAME  |  h(CHILD,CHILD,...)  |  v(CHILD,CHILD,...)
+//              CHILD := <SIZE:>NODE        with SIZE either a percentage (60%) or a number of cells (80)
+//
+// "h" puts its children side by side, "v" stacks them, names are only labels for the panes.
+// Example:     h(60%:v(a,b),c)
+
+pub fn parse_split_dsl(s: &str) -> Result<LayoutSpec, Errcode> {
+    let input: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let res = match all_consuming(dsl_node)(&input) {
+        Ok((_, spec)) => Ok(spec),
+        Err(e) => Err(Errcode::LayoutError(
+            format!("Invalid layout description \"{}\": {}", s, Errcode::from(e).details())
+        )),
+    };
+    res
+}
+
+fn dsl_direction(input: &str) -> IResult<&str, SplitDirection> {
+    map(terminated(one_of("hv"), char('(')), |c| match c {
+        'h' => SplitDirection::Horizontal,
+        _ => SplitDirection::Vertical,
+    })(input)
+}
+
+fn dsl_size(input: &str) -> IResult<&str, CellSize> {
+    let (input, n) = map_res(digit1, usize::from_str)(input)?;
+    let (input, percent) = opt(char('%'))(input)?;
+    let (input, _) = char(':')(input)?;
+    Ok((input, if percent.is_some() { CellSize::Percent(n) } else { CellSize::Cells(n) }))
+}
+
+fn dsl_child(input: &str) -> IResult<&str, (CellSize, LayoutSpec)> {
+    map(pair(opt(dsl_size), dsl_node), |(size, node)| (size.unwrap_or(CellSize::Auto), node))(input)
+}
+
+fn dsl_node(input: &str) -> IResult<&str, LayoutSpec> {
+    alt((
+        map(
+            pair(dsl_direction, terminated(separated_list1(char(','), dsl_child), char(')'))),
+            |(dir, children)| LayoutSpec::Split(dir, children)
+        ),
+        map(
+            take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
+            |_| LayoutSpec::Pane
+        ),
+    ))(input)
+}
+
+
+
+
+
+#[test]
+fn test_split_dsl(){
+    let spec = parse_split_dsl("h(60%:v(a,b), c)").unwrap();
+    assert_eq!(spec, LayoutSpec::Split(SplitDirection::Horizontal, vec![
+        (CellSize::Percent(60), LayoutSpec::Split(SplitDirection::Vertical, vec![
+            (CellSize::Auto, LayoutSpec::Pane),
+            (CellSize::Auto, LayoutSpec::Pane),
+        ])),
+        (CellSize::Auto, LayoutSpec::Pane),
+    ]));
+
+    assert_eq!(parse_split_dsl("editor").unwrap(), LayoutSpec::Pane);
+    assert_eq!(parse_split_dsl("v(20:a,b)").unwrap(), LayoutSpec::Split(SplitDirection::Vertical, vec![
+        (CellSize::Cells(20), LayoutSpec::Pane),
+        (CellSize::Auto, LayoutSpec::Pane),
+    ]));
+
+    for wrong in ["", "h()", "h(a,b", "x(a,b)", "h(a,,b)", "h(%:a)"].iter() {
+        assert!(parse_split_dsl(wrong).is_err(), "{}", wrong);
+    }
+}
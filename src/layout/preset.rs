@@ -0,0 +1,129 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::Errcode;
+use crate::layout::{CellSize, LayoutSpec, SplitDirection, TermSize};
+
+// Default values of the "main-pane-width" and "main-pane-height" tmux options
+const MAIN_PANE_WIDTH: usize = 80;
+const MAIN_PANE_HEIGHT: usize = 24;
+
+/// The layouts tmux can apply with `select-layout <name>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutPreset {
+    EvenHorizontal,
+    EvenVertical,
+    MainHorizontal,
+    MainVertical,
+    Tiled,
+}
+
+impl LayoutPreset {
+    pub fn to_spec(self, size: &TermSize, npanes: usize) -> LayoutSpec {
+        if npanes <= 1 {
+            return LayoutSpec::Pane;
+        }
+
+        match self {
+            LayoutPreset::EvenHorizontal => LayoutSpec::even(SplitDirection::Horizontal, npanes),
+            LayoutPreset::EvenVertical => LayoutSpec::even(SplitDirection::Vertical, npanes),
+            LayoutPreset::MainHorizontal => LayoutSpec::Split(SplitDirection::Vertical, vec![
+                (CellSize::Cells(main_pane_size(MAIN_PANE_HEIGHT, size.height)), LayoutSpec::Pane),
+                (CellSize::Auto, LayoutSpec::even(SplitDirection::Horizontal, npanes - 1)),
+            ]),
+            LayoutPreset::MainVertical => LayoutSpec::Split(SplitDirection::Horizontal, vec![
+                (CellSize::Cells(main_pane_size(MAIN_PANE_WIDTH, size.width)), LayoutSpec::Pane),
+                (CellSize::Auto, LayoutSpec::even(SplitDirection::Vertical, npanes - 1)),
+            ]),
+            LayoutPreset::Tiled => tiled(npanes),
+        }
+    }
+}
+
+// Leave at least 1 cell for the other panes, and 1 for the border
+fn main_pane_size(wanted: usize, total: usize) -> usize {
+    wanted.min(total.saturating_sub(2)).max(1)
+}
+
+// Same grid as tmux: add rows and columns alternatively until all the panes fit
+fn tiled(npanes: usize) -> LayoutSpec {
+    let (mut rows, mut columns) = (1, 1);
+    while rows * columns < npanes {
+        rows += 1;
+        if rows * columns < npanes {
+            columns += 1;
+        }
+    }
+
+    let mut left = npanes;
+    let mut lines = vec![];
+    while left > 0 {
+        let n = left.min(columns);
+        lines.push((CellSize::Auto, LayoutSpec::even(SplitDirection::Horizontal, n)));
+        left -= n;
+    }
+
+    if lines.len() == 1 {
+        lines.pop().unwrap().1
+    } else {
+        LayoutSpec::Split(SplitDirection::Vertical, lines)
+    }
+}
+
+impl FromStr for LayoutPreset {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<LayoutPreset, Errcode> {
+        match s {
+            "even-horizontal" => Ok(LayoutPreset::EvenHorizontal),
+            "even-vertical" => Ok(LayoutPreset::EvenVertical),
+            "main-horizontal" => Ok(LayoutPreset::MainHorizontal),
+            "main-vertical" => Ok(LayoutPreset::MainVertical),
+            "tiled" => Ok(LayoutPreset::Tiled),
+            _ => Err(Errcode::LayoutError(format!("Unknown layout preset \"{}\"", s))),
+        }
+    }
+}
+
+impl fmt::Display for LayoutPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            LayoutPreset::EvenHorizontal => "even-horizontal",
+            LayoutPreset::EvenVertical => "even-vertical",
+            LayoutPreset::MainHorizontal => "main-horizontal",
+            LayoutPreset::MainVertical => "main-vertical",
+            LayoutPreset::Tiled => "tiled",
+        })
+    }
+}
+
+
+
+
+
+#[test]
+fn test_presets_nb_panes(){
+    let size = TermSize { width: 211, height: 62 };
+    let presets = [
+        LayoutPreset::EvenHorizontal, LayoutPreset::EvenVertical,
+        LayoutPreset::MainHorizontal, LayoutPreset::MainVertical, LayoutPreset::Tiled,
+    ];
+
+    for preset in presets.iter() {
+        assert_eq!(LayoutPreset::from_str(&preset.to_string()).unwrap(), *preset);
+        for npanes in 1..10 {
+            let layout = preset.to_spec(&size, npanes).build(&size).unwrap();
+            assert_eq!(layout.nb_panes(), npanes, "{:?}", preset);
+        }
+    }
+}
+
+#[test]
+fn test_presets_main_pane(){
+    let size = TermSize { width: 211, height: 62 };
+    let layout = LayoutPreset::MainVertical.to_spec(&size, 3).build(&size).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0{80x62,0,0,0,130x62,81,0[130x31,81,0,1,130x30,81,32,2]}");
+
+    let layout = LayoutPreset::Tiled.to_spec(&size, 3).build(&size).unwrap();
+    assert_eq!(layout.root.to_string(), "211x62,0,0[211x31,0,0{105x31,0,0,0,105x31,106,0,1},211x30,0,32,2]");
+}
@@ -6,9 +6,15 @@ mod session;
 mod cli;
 mod pane;
 mod window;
+mod layout;
+mod options;
+mod preview;
+mod reconcile;
+mod prompt;
+mod tmux;
+mod procs;
 mod serialisation;
-
-extern crate text_io;
+mod interop;
 
 use errors::handle_error;
 use cli::Commands;
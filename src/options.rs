@@ -0,0 +1,316 @@
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use std::str::FromStr;
+
+use crate::errors::Errcode;
+use crate::serialisation::remove_key;
+
+const FLAG_ON: &str = "on";
+const FLAG_OFF: &str = "off";
+
+/// Values accepted by a tmux option
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OptionType {
+    /// on / off
+    Flag,
+    Number,
+    /// Number of cells, or percentage of the window
+    Size,
+    Choice(&'static [&'static str]),
+    Text,
+}
+
+const WINDOW_OPTIONS: [(&str, OptionType); 34] = [
+    ("aggressive-resize", OptionType::Flag),
+    ("allow-rename", OptionType::Flag),
+    ("alternate-screen", OptionType::Flag),
+    ("automatic-rename", OptionType::Flag),
+    ("automatic-rename-format", OptionType::Text),
+    ("clock-mode-colour", OptionType::Text),
+    ("clock-mode-style", OptionType::Choice(&["12", "24"])),
+    ("main-pane-height", OptionType::Size),
+    ("main-pane-width", OptionType::Size),
+    ("mode-keys", OptionType::Choice(&["vi", "emacs"])),
+    ("mode-style", OptionType::Text),
+    ("monitor-activity", OptionType::Flag),
+    ("monitor-bell", OptionType::Flag),
+    ("monitor-silence", OptionType::Number),
+    ("other-pane-height", OptionType::Size),
+    ("other-pane-width", OptionType::Size),
+    ("pane-active-border-style", OptionType::Text),
+    ("pane-base-index", OptionType::Number),
+    ("pane-border-format", OptionType::Text),
+    ("pane-border-lines", OptionType::Choice(&["single", "double", "heavy", "simple", "number"])),
+    ("pane-border-status", OptionType::Choice(&["off", "top", "bottom"])),
+    ("pane-border-style", OptionType::Text),
+    ("remain-on-exit", OptionType::Choice(&[FLAG_ON, FLAG_OFF, "failed"])),
+    ("scroll-on-clear", OptionType::Flag),
+    ("synchronize-panes", OptionType::Flag),
+    ("window-active-style", OptionType::Text),
+    ("window-size", OptionType::Choice(&["largest", "smallest", "manual", "latest"])),
+    ("window-status-current-format", OptionType::Text),
+    ("window-status-current-style", OptionType::Text),
+    ("window-status-format", OptionType::Text),
+    ("window-status-separator", OptionType::Text),
+    ("window-status-style", OptionType::Text),
+    ("window-style", OptionType::Text),
+    ("wrap-search", OptionType::Flag),
+];
+
+const SESSION_OPTIONS: [(&str, OptionType); 28] = [
+    ("activity-action", OptionType::Choice(&["any", "none", "current", "other"])),
+    ("base-index", OptionType::Number),
+    ("bell-action", OptionType::Choice(&["any", "none", "current", "other"])),
+    ("default-command", OptionType::Text),
+    ("default-shell", OptionType::Text),
+    ("default-terminal", OptionType::Text),
+    ("destroy-unattached", OptionType::Flag),
+    ("detach-on-destroy", OptionType::Choice(&[FLAG_ON, FLAG_OFF, "no-detached", "previous", "next"])),
+    ("display-panes-time", OptionType::Number),
+    ("display-time", OptionType::Number),
+    ("escape-time", OptionType::Number),
+    ("focus-events", OptionType::Flag),
+    ("history-limit", OptionType::Number),
+    ("mouse", OptionType::Flag),
+    ("prefix", OptionType::Text),
+    ("renumber-windows", OptionType::Flag),
+    ("repeat-time", OptionType::Number),
+    ("set-clipboard", OptionType::Choice(&[FLAG_ON, FLAG_OFF, "external"])),
+    ("set-titles", OptionType::Flag),
+    ("set-titles-string", OptionType::Text),
+    ("status", OptionType::Choice(&[FLAG_ON, FLAG_OFF, "2", "3", "4", "5"])),
+    ("status-interval", OptionType::Number),
+    ("status-keys", OptionType::Choice(&["vi", "emacs"])),
+    ("status-left", OptionType::Text),
+    ("status-position", OptionType::Choice(&["top", "bottom"])),
+    ("status-right", OptionType::Text),
+    ("status-style", OptionType::Text),
+    ("visual-activity", OptionType::Choice(&[FLAG_ON, FLAG_OFF, "both"])),
+];
+
+/// Where a set of options applies, each one has its own known options
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionScope {
+    Window,
+    Session,
+}
+
+impl OptionScope {
+    fn option_type(self, name: &str) -> Option<OptionType> {
+        let known: &[(&str, OptionType)] = match self {
+            OptionScope::Window => &WINDOW_OPTIONS,
+            OptionScope::Session => &SESSION_OPTIONS,
+        };
+        known.iter().find(|(n, _)| *n == name).map(|(_, t)| *t)
+    }
+}
+
+fn flag_value(on: bool) -> Value {
+    Value::from(if on { FLAG_ON } else { FLAG_OFF })
+}
+
+fn parse_flag(val: &Value) -> Option<bool> {
+    match val {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => n.as_u64().filter(|n| *n <= 1).map(|n| n == 1),
+        Value::String(s) => match s.as_str() {
+            FLAG_ON | "yes" | "true" | "1" => Some(true),
+            FLAG_OFF | "no" | "false" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Numbers given as strings are written as JSON numbers
+fn parse_number(val: &Value) -> Option<Value> {
+    match val {
+        Value::Number(n) if n.is_u64() => Some(val.clone()),
+        Value::String(s) => u64::from_str(s).ok().map(Value::from),
+        _ => None,
+    }
+}
+
+/// The value written in the file for an option set from the commandline, booleans become on / off
+fn normalise(scope: OptionScope, name: &str, val: Value) -> Result<Value, Errcode> {
+    let invalid = |expected: &str| Errcode::ParsingError(
+        format!("Invalid value {} for option \"{}\", expected {}", val, name, expected)
+    );
+    let opt_type = match scope.option_type(name) {
+        Some(t) => t,
+        None => return Ok(match val {
+            Value::Bool(b) => flag_value(b),
+            _ => val,
+        }),
+    };
+
+    match opt_type {
+        OptionType::Flag => parse_flag(&val).map(flag_value).ok_or_else(|| invalid("on or off")),
+        OptionType::Number => parse_number(&val).ok_or_else(|| invalid("a number")),
+        OptionType::Size => match &val {
+            Value::String(s) if s.strip_suffix('%').map(|p| u64::from_str(p).is_ok()).unwrap_or(false) => Ok(val.clone()),
+            _ => parse_number(&val).ok_or_else(|| invalid("a number of cells or a percentage")),
+        },
+        OptionType::Choice(choices) => {
+            let choice = match &val {
+                Value::Bool(b) => flag_value(*b),
+                Value::Number(n) => Value::from(n.to_string()),
+                _ => val.clone(),
+            };
+            match choice.as_str() {
+                Some(c) if choices.contains(&c) => Ok(if val.is_number() { val.clone() } else { choice }),
+                _ => Err(invalid(&choices.join(", "))),
+            }
+        },
+        OptionType::Text => match val {
+            Value::String(_) | Value::Number(_) => Ok(val),
+            _ => Err(invalid("a string")),
+        },
+    }
+}
+
+/// Options of a tmux window or session, kept in the order they are written
+/// Values of known options are validated, any other option is accepted as is
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmuxOptions {
+    scope: OptionScope,
+    values: Map<String, Value>,
+}
+
+impl TmuxOptions {
+    pub fn new(scope: OptionScope) -> TmuxOptions {
+        TmuxOptions { scope, values: Map::new() }
+    }
+
+    /// The values are kept as they are written, invalid values of known options are only reported
+    pub fn from_json(scope: OptionScope, val: &Value) -> Result<TmuxOptions, Errcode> {
+        let map = val.as_object().ok_or_else(|| Errcode::JsonError("Json loading options".to_string()))?;
+        for (name, val) in map.iter() {
+            if let Err(e) = normalise(scope, name, val.clone()) {
+                eprintln!("Warning: {}", e);
+            }
+        }
+        Ok(TmuxOptions { scope, values: map.clone() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The options to write in a file, if there is any
+    pub fn to_json(&self) -> Option<Value> {
+        if self.is_empty() { None } else { Some(Value::Object(self.values.clone())) }
+    }
+
+    /// The options, in the order they are written
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.values.iter()
+    }
+
+    pub fn get_flag(&self, name: &str) -> Option<bool> {
+        self.values.get(name).and_then(parse_flag)
+    }
+
+    pub fn set_flag(&mut self, name: &str, on: bool) {
+        self.values.insert(name.to_string(), flag_value(on));
+    }
+
+    /// Sets the option from its value on the commandline
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), Errcode> {
+        let val = normalise(self.scope, name, Value::from(value))?;
+        self.values.insert(name.to_string(), val);
+        Ok(())
+    }
+
+    pub fn unset(&mut self, name: &str) -> Result<(), Errcode> {
+        remove_key(&mut self.values, name).ok_or_else(|| Errcode::OptionNotFound(name.to_string()))?;
+        Ok(())
+    }
+}
+
+impl Serialize for TmuxOptions {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.values.serialize(serializer)
+    }
+}
+
+/// Option or variable set from the commandline, format:      NAME=VALUE
+#[derive(Debug)]
+pub struct OptionAssignment {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for OptionAssignment {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<OptionAssignment, Errcode> {
+        let (name, value) = s.split_once('=')
+            .ok_or(Errcode::ArgValidationError("option, expected NAME=VALUE"))?;
+        if name.is_empty() {
+            return Err(Errcode::ArgValidationError("option, expected NAME=VALUE"));
+        }
+        Ok(OptionAssignment { name: name.to_string(), value: value.to_string() })
+    }
+}
+
+#[test]
+fn test_window_options(){
+    let json = serde_json::json!({
+        "automatic-rename": true,
+        "main-pane-width": "80",
+        "other-pane-height": "30%",
+        "remain-on-exit": "failed",
+        "clock-mode-style": 24,
+        "synchronize-panes": "wrong",
+        "my-plugin-option": false,
+        "@user-option": "anything",
+    });
+    let mut options = TmuxOptions::from_json(OptionScope::Window, &json).unwrap();
+    // Loaded values are written back as they are
+    assert_eq!(serde_json::to_value(&options).unwrap(), json);
+    assert_eq!(options.get_flag("automatic-rename"), Some(true));
+
+    options.set("synchronize-panes", "yes").unwrap();
+    options.set("pane-border-format", "#{pane_index} #{pane_current_command}").unwrap();
+    options.set("main-pane-width", "50%").unwrap();
+    options.set("my-plugin-option", "off").unwrap();
+    assert!(options.set("main-pane-width", "wide").is_err());
+    assert!(options.set("mode-keys", "nano").is_err());
+    assert!(options.set("monitor-activity", "maybe").is_err());
+    assert_eq!(options.get_flag("synchronize-panes"), Some(true));
+    let written = serde_json::to_value(&options).unwrap();
+    assert_eq!((&written["synchronize-panes"], &written["my-plugin-option"]), (&Value::from("on"), &Value::from("off")));
+
+    options.unset("@user-option").unwrap();
+    assert!(matches!(options.unset("@user-option"), Err(Errcode::OptionNotFound(_))));
+    assert!(TmuxOptions::from_json(OptionScope::Window, &serde_json::json!(["on"])).is_err());
+}
+
+#[test]
+fn test_session_options(){
+    let mut options = TmuxOptions::new(OptionScope::Session);
+    options.set("mouse", "on").unwrap();
+    options.set("base-index", "1").unwrap();
+    assert!(options.set("status-position", "left").is_err());
+    assert!(options.set("history-limit", "lots").is_err());
+    options.set("main-pane-width", "wide").unwrap();
+    assert_eq!(serde_json::to_value(&options).unwrap(), serde_json::json!({
+        "mouse": "on",
+        "base-index": 1,
+        "main-pane-width": "wide",
+    }));
+}
+
+#[test]
+fn test_option_assignment(){
+    assert!(OptionAssignment::from_str("=on").is_err());
+    assert!(OptionAssignment::from_str("mouse").is_err());
+    let assignment = OptionAssignment::from_str("mouse=on").unwrap();
+    assert_eq!((assignment.name.as_str(), assignment.value.as_str()), ("mouse", "on"));
+    assert_eq!(OptionAssignment::from_str("pane-border-format=a=b").unwrap().value, "a=b");
+}
@@ -1,102 +1,400 @@
-use serde::{Serializer, Serialize, Deserialize};
-use serde::ser::{SerializeStruct, SerializeSeq};
+use serde::{Serializer, Serialize};
+use serde::ser::{SerializeMap, SerializeSeq};
 use serde_json::{Map, Value};
 
+use std::path::PathBuf;
+
 use crate::errors::Errcode;
-use crate::serialisation::strval_to_string;
+use crate::serialisation::{strval_to_string, val_to_bool, written_entries, Entries};
+
+// Separates the commands of a pane when they are displayed or edited as a single line
+const CMDS_SEP: &str = "; ";
+// Chains the steps of a command, each one only runs if the previous one succeeded
+const STEPS_SEP: &str = "&&";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TmuxPane {
+    /// Commands sent to the pane one after the other
+    pub shell_command: Vec<String>,
+    /// Relative to the start directory of the window
+    pub start_directory: Option<PathBuf>,
+    pub focus: bool,
+    /// Seconds to wait before / after sending the commands
+    pub sleep_before: Option<f64>,
+    pub sleep_after: Option<f64>,
+    /// Whether the commands are executed, or only typed in the pane
+    pub enter: Option<bool>,
+    /// Keys this tool doesn't know about, written back as they were
+    pub unknown_keys: Map<String, Value>,
+    /// The pane as it is written in the file it was loaded from
+    pub written: Option<Value>,
+}
+
+pub fn commands_from_json(val: &Value) -> Result<Vec<String>, Errcode> {
+    match val {
+        Value::Null => Ok(vec![]),
+        Value::String(cmd) if cmd.is_empty() => Ok(vec![]),
+        Value::String(cmd) => Ok(vec![cmd.clone()]),
+        Value::Array(cmds) => cmds.iter().map(strval_to_string).collect(),
+        _ => Err(Errcode::JsonError(format!("Invalid shell_command {}", val))),
+    }
+}
+
+/// Splits a command in the steps chained with "&&" outside of quotes
+pub fn command_steps(cmd: &str) -> Vec<String> {
+    let mut steps = vec![];
+    let mut step = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = cmd.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quote != Some('\'') => {
+                step.push(c);
+                step.extend(chars.next());
+            },
+            '\'' | '"' if quote.is_none() => { quote = Some(c); step.push(c); },
+            c if quote == Some(c) => { quote = None; step.push(c); },
+            '&' if quote.is_none() && chars.peek() == Some(&'&') => {
+                chars.next();
+                steps.push(step.trim().to_string());
+                step.clear();
+            },
+            c => step.push(c),
+        }
+    }
+    steps.push(step.trim().to_string());
+    steps.into_iter().filter(|s| !s.is_empty()).collect()
+}
+
+/// The first elements shared by all the lists
+pub fn common_prefix(lists: &[Vec<String>]) -> Vec<String> {
+    let first = match lists.first() {
+        Some(f) => f,
+        None => return vec![],
+    };
+    let len = lists.iter().map(|l| first.iter().zip(l.iter()).take_while(|(a, b)| a == b).count())
+        .min().unwrap_or(0);
+    first[..len].to_vec()
+}
 
-// List of commands
-pub type TmuxPane = String;
+fn seconds_from_json(key: &str, val: &Value) -> Result<f64, Errcode> {
+    val.as_f64().ok_or_else(|| Errcode::JsonError(format!("{} is not a number of seconds", key)))
+}
 
-#[derive(Deserialize, Debug)]
-pub struct FocusedPane {
-    shell_command: String,
-    focus: bool
+// Whole seconds are written as integers, the way they are usually written by hand
+fn seconds_to_json(secs: f64) -> Value {
+    if secs.fract() == 0.0 && secs >= 0.0 {
+        Value::from(secs as u64)
+    } else {
+        Value::from(secs)
+    }
 }
 
-impl FocusedPane {
-    pub fn from_cmd(cmd: TmuxPane) -> FocusedPane {
-        FocusedPane {
-            shell_command: cmd,
-            focus: true
+impl TmuxPane {
+    pub fn from_cmd(cmd: String) -> TmuxPane {
+        TmuxPane {
+            shell_command: if cmd.is_empty() { vec![] } else { vec![cmd] },
+            ..TmuxPane::default()
         }
     }
 
-    pub fn from_json(val: &Map<String, Value>) -> Result<FocusedPane, Errcode> {
-        let mut pane = FocusedPane::from_cmd("".to_string());
-        for (key, val) in val.iter() {
+    // Format:      "CMD", null, or {"shell_command": "CMD" | ["CMD", ...], "start_directory": "DIR", "focus": true,
+    //                               "sleep_before": SECS, "sleep_after": SECS, "enter": false}
+    pub fn from_json(val: &Value) -> Result<TmuxPane, Errcode> {
+        let map = match val {
+            Value::Null | Value::String(_) => return Ok(TmuxPane {
+                shell_command: commands_from_json(val)?,
+                written: Some(val.clone()),
+                ..TmuxPane::default()
+            }),
+            Value::Object(map) => map,
+            _ => return Err(Errcode::JsonError(format!("Invalid pane {}", val))),
+        };
+
+        let mut pane = TmuxPane { written: Some(val.clone()), ..TmuxPane::default() };
+        for (key, val) in map.iter() {
             match key.as_ref() {
-                "focus" => pane.focus = strval_to_string(val)? == "true",
-                "shell_command" => pane.shell_command = strval_to_string(val)?,
-                _ => return Err(Errcode::JsonError("FocusedPane from Json".to_string())),
+                "shell_command" => pane.shell_command = commands_from_json(val)?,
+                "start_directory" => pane.start_directory = Some(PathBuf::from(strval_to_string(val)?)),
+                "focus" => pane.focus = val_to_bool(val)?,
+                "sleep_before" => pane.sleep_before = Some(seconds_from_json(key, val)?),
+                "sleep_after" => pane.sleep_after = Some(seconds_from_json(key, val)?),
+                "enter" => pane.enter = Some(val_to_bool(val)?),
+                _ => { pane.unknown_keys.insert(key.clone(), val.clone()); },
             }
         }
         Ok(pane)
     }
+
+    /// The commands of the pane on a single line
+    pub fn command(&self) -> String {
+        self.shell_command.join(CMDS_SEP)
+    }
+
+    /// The steps of all the commands of the pane, in the order they run
+    fn steps(&self) -> Vec<String> {
+        self.shell_command.iter().flat_map(|c| command_steps(c)).collect()
+    }
+
+    /// Removes the first steps of the commands, the commands only partly removed keep their other steps
+    fn drop_steps(&mut self, nsteps: usize) {
+        let mut left = nsteps;
+        let mut cmds = vec![];
+        for cmd in self.shell_command.iter() {
+            let steps = command_steps(cmd);
+            if left == 0 {
+                cmds.push(cmd.clone());
+            } else if left < steps.len() {
+                cmds.push(steps[left..].join(&format!(" {} ", STEPS_SEP)));
+                left = 0;
+            } else {
+                left -= steps.len();
+            }
+        }
+        self.shell_command = cmds;
+    }
+
+    // Panes with a single command and nothing else are written as a simple string
+    fn is_simple(&self) -> bool {
+        self.shell_command.len() <= 1 && !self.focus && self.start_directory.is_none()
+            && self.sleep_before.is_none() && self.sleep_after.is_none() && self.enter.is_none()
+            && self.unknown_keys.is_empty()
+    }
+
+    // The known keys with the value written for each of them
+    fn entries(&self) -> Entries {
+        let cmds = if self.shell_command.len() > 1 { Value::from(self.shell_command.clone()) } else { Value::from(self.command()) };
+        vec![
+            ("shell_command", Some(cmds)),
+            ("start_directory", self.start_directory.as_ref().map(|dir| serde_json::json!(dir.to_str()))),
+            ("focus", if self.focus { Some(Value::from("true")) } else { None }),
+            ("sleep_before", self.sleep_before.map(seconds_to_json)),
+            ("sleep_after", self.sleep_after.map(seconds_to_json)),
+            ("enter", self.enter.map(Value::from)),
+        ]
+    }
+
+    // Whether the value written in the file still holds the value of the key
+    fn is_written_value(&self, key: &str, val: &Value) -> Result<bool, Errcode> {
+        Ok(match key {
+            "shell_command" => commands_from_json(val)? == self.shell_command,
+            "start_directory" => Some(PathBuf::from(strval_to_string(val)?)) == self.start_directory,
+            "focus" => val_to_bool(val)? == self.focus,
+            "sleep_before" => Some(seconds_from_json(key, val)?) == self.sleep_before,
+            "sleep_after" => Some(seconds_from_json(key, val)?) == self.sleep_after,
+            "enter" => Some(val_to_bool(val)?) == self.enter,
+            _ => false,
+        })
+    }
 }
 
-impl Serialize for FocusedPane{
+impl Serialize for TmuxPane{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("FocusedPane", 2)?;
-        state.serialize_field("shell_command", &self.shell_command)?;
-        state.serialize_field("focus", &self.focus.to_string())?;
+        let written = match &self.written {
+            Some(Value::Object(map)) => Some(map),
+            // Simple panes keep their form, a string or null
+            Some(val) if self.is_simple() && commands_from_json(val).ok().as_ref() == Some(&self.shell_command) => {
+                return val.serialize(serializer);
+            },
+            _ => None,
+        };
+        if written.is_none() && self.is_simple() {
+            return serializer.serialize_str(&self.command());
+        }
+
+        let entries = written_entries(self.entries(), TmuxPane::default().entries(), &self.unknown_keys, written,
+            |key, val| self.is_written_value(key, val));
+        let mut state = serializer.serialize_map(Some(entries.len()))?;
+        for (key, val) in entries.iter() {
+            state.serialize_entry(key, val)?;
+        }
         state.end()
     }
 }
 
 #[derive(Debug)]
 pub struct PaneSerializer {
-    focused: FocusedPane,
+    focused: TmuxPane,
     others: Vec<TmuxPane>,
-    focused_index: usize
+    focused_index: usize,
+    // No pane is focused in the file, tmuxp focuses the first one
+    implicit_focus: bool,
 }
 
 impl PaneSerializer{
-    pub fn create(focused: FocusedPane, focused_index: usize, others: Vec<TmuxPane>) -> PaneSerializer {
+    pub fn create(focused: TmuxPane, focused_index: usize, others: Vec<TmuxPane>) -> PaneSerializer {
+        let mut focused = focused;
+        focused.focus = true;
         PaneSerializer {
             focused,
             focused_index,
-            others
+            others,
+            implicit_focus: false,
         }
     }
 
+    /// The panes of a file, tmuxp focuses the first pane if none is focused, the last one wins if several are
+    pub fn from_loaded(panes: Vec<TmuxPane>) -> PaneSerializer {
+        let focus = panes.iter().rposition(|p| p.focus);
+        let mut serializer = PaneSerializer::from_panes(panes, focus.unwrap_or(0));
+        serializer.implicit_focus = focus.is_none();
+        serializer
+    }
+
+    /// Panes executing the commands, in the order of the window
+    pub fn from_cmds(cmds: Vec<String>, focus: usize) -> PaneSerializer {
+        PaneSerializer::from_panes(cmds.into_iter().map(TmuxPane::from_cmd).collect(), focus)
+    }
+
+    /// The panes, in the order of the window
+    pub fn from_panes(panes: Vec<TmuxPane>, focus: usize) -> PaneSerializer {
+        assert!(focus < panes.len());
+        let mut serializer = PaneSerializer::create(TmuxPane::default(), 0, vec![]);
+        serializer.rebuild(panes, focus);
+        serializer
+    }
+
     pub fn nb_panes(&self) -> usize {
         1 + self.others.len()
     }
 
-    pub fn set_panes_cmds(&mut self, cmds: &Vec<String>) {
-        assert!(cmds.len() >= 1);
-        let mut focused_passed = 0;
-        self.others.resize(cmds.len()-1, "".to_string());
-        
-        for (n,c) in cmds.iter().enumerate() {
-            if n == self.focused_index{
-                self.focused.shell_command = c.clone();
-                focused_passed = 1;
-            } else { // if (n-focused_passed) < self.others.len() {
-                *self.others.get_mut(n-focused_passed).unwrap() = c.clone();
-            }
-        }
+    /// Sets the commands of all the panes, the focus stays on the same index if it still exists
+    /// The panes keeping the same command keep their settings, even if they moved
+    pub fn set_panes_cmds(&mut self, cmds: &[String]) {
+        assert!(!cmds.is_empty());
+        let focus = self.focused_index.min(cmds.len() - 1);
+        let mut old_panes: Vec<Option<TmuxPane>> = self.get_panes().into_iter().map(Some).collect();
+        let panes = cmds.iter().map(|cmd| {
+            old_panes.iter_mut()
+                .find(|p| p.as_ref().map(|p| p.command() == *cmd).unwrap_or(false))
+                .and_then(|p| p.take())
+                .unwrap_or_else(|| TmuxPane::from_cmd(cmd.clone()))
+        }).collect();
+        self.rebuild(panes, focus);
     }
 
+    /// Focuses a pane, the panes keep their order
     pub fn set_focus(&mut self, focus: usize) -> Result<(), Errcode>{
-        if focus == self.focused_index{
-            return Ok(());
+        self.check_index(focus)?;
+        let panes = self.get_panes();
+        self.rebuild(panes, focus);
+        Ok(())
+    }
+
+    pub fn focused_index(&self) -> usize {
+        self.focused_index
+    }
+
+    // Rebuilds the panes, in the order of the window
+    fn rebuild(&mut self, mut panes: Vec<TmuxPane>, focus: usize) {
+        for (n, p) in panes.iter_mut().enumerate() {
+            p.focus = n == focus;
+        }
+        self.focused = panes.remove(focus);
+        self.focused_index = focus;
+        self.others = panes;
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), Errcode> {
+        if index >= self.nb_panes() {
+            return Err(Errcode::LayoutError(format!("No pane {} in a window of {} panes", index, self.nb_panes())));
         }
-        let mut cmds = self.get_panes_cmds()?;
-        let focused = cmds.remove(focus);
-        self.others = cmds;
-        self.focused = FocusedPane::from_cmd(focused);
         Ok(())
     }
 
-    pub fn get_panes_cmds(&mut self) -> Result<Vec<String>, Errcode>{
-        let mut allcmds = self.others.clone();
-        allcmds.insert(0, self.focused.shell_command.clone());
-        Ok(allcmds)
+    pub fn insert_pane(&mut self, index: usize, pane: TmuxPane) -> Result<(), Errcode> {
+        if index > self.nb_panes() {
+            return Err(Errcode::LayoutError(format!("Cannot insert pane {} in a window of {} panes", index, self.nb_panes())));
+        }
+        let mut panes = self.get_panes();
+        panes.insert(index, pane);
+        let focus = if index <= self.focused_index { self.focused_index + 1 } else { self.focused_index };
+        self.rebuild(panes, focus);
+        Ok(())
+    }
+
+    /// Removes the pane, the focus goes to the next pane if the focused one is removed
+    pub fn remove_pane(&mut self, index: usize) -> Result<(), Errcode> {
+        self.check_index(index)?;
+        if self.nb_panes() == 1 {
+            return Err(Errcode::LayoutError("Cannot remove the last pane of a window".to_string()));
+        }
+        let mut panes = self.get_panes();
+        panes.remove(index);
+        let focus = if index < self.focused_index { self.focused_index - 1 } else { self.focused_index };
+        let nb = panes.len();
+        self.rebuild(panes, focus.min(nb - 1));
+        Ok(())
+    }
+
+    /// Swaps two panes, the focus follows its pane
+    pub fn swap_panes(&mut self, a: usize, b: usize) -> Result<(), Errcode> {
+        self.check_index(a)?;
+        self.check_index(b)?;
+        let mut panes = self.get_panes();
+        panes.swap(a, b);
+        let focus = match self.focused_index {
+            f if f == a => b,
+            f if f == b => a,
+            f => f,
+        };
+        self.rebuild(panes, focus);
+        Ok(())
+    }
+
+    /// Moves a pane to another position, the panes in between are shifted, the focus follows its pane
+    pub fn move_pane(&mut self, from: usize, to: usize) -> Result<(), Errcode> {
+        self.check_index(from)?;
+        self.check_index(to)?;
+        let mut panes = self.get_panes();
+        let pane = panes.remove(from);
+        panes.insert(to, pane);
+        let focus = match self.focused_index {
+            f if f == from => to,
+            f if from < f && f <= to => f - 1,
+            f if to <= f && f < from => f + 1,
+            f => f,
+        };
+        self.rebuild(panes, focus);
+        Ok(())
+    }
+
+    /// Replaces the commands of a pane, it keeps its other settings
+    pub fn set_pane_cmd(&mut self, index: usize, cmd: String) -> Result<(), Errcode> {
+        self.check_index(index)?;
+        let mut panes = self.get_panes();
+        panes[index].shell_command = TmuxPane::from_cmd(cmd).shell_command;
+        self.rebuild(panes, self.focused_index);
+        Ok(())
+    }
+
+    /// All the panes, in the order of the panes in the window
+    pub fn get_panes(&self) -> Vec<TmuxPane> {
+        let mut allpanes = self.others.clone();
+        allpanes.insert(self.focused_index.min(allpanes.len()), self.focused.clone());
+        allpanes
+    }
+
+    /// Removes the first steps shared by the commands of all the panes, and returns them
+    pub fn hoist_common_steps(&mut self) -> Vec<String> {
+        if self.nb_panes() < 2 {
+            return vec![];
+        }
+        let mut panes = self.get_panes();
+        let prefix = common_prefix(&panes.iter().map(|p| p.steps()).collect::<Vec<Vec<String>>>());
+        for p in panes.iter_mut() {
+            p.drop_steps(prefix.len());
+        }
+        self.rebuild(panes, self.focused_index);
+        prefix
+    }
+
+    /// The commands of all the panes, in the order of the panes in the window
+    pub fn get_panes_cmds(&self) -> Result<Vec<String>, Errcode>{
+        Ok(self.get_panes().iter().map(|p| p.command()).collect())
     }
 }
 
@@ -110,7 +408,11 @@ impl Serialize for PaneSerializer{
         let mut ind_back = 0;
         for i in 0..(1+self.others.len()){
             if i == self.focused_index {
-                seq.serialize_element(&self.focused)?;
+                if self.implicit_focus && i == 0 {
+                    seq.serialize_element(&TmuxPane { focus: false, ..self.focused.clone() })?;
+                } else {
+                    seq.serialize_element(&self.focused)?;
+                }
                 ind_back = 1;
             } else {
                 assert!(i - ind_back < self.others.len());
@@ -122,3 +424,181 @@ impl Serialize for PaneSerializer{
     }
 }
 
+
+
+
+
+#[test]
+fn test_pane_forms(){
+    let json: Value = serde_json::from_str(r#"[
+        "htop",
+        null,
+        {"shell_command": ["cd api", "source .env", "make run"], "start_directory": "api"},
+        {"shell_command": "cargo watch -c", "focus": true, "sleep_before": 2, "sleep_after": 0.5, "enter": false}
+    ]"#).unwrap();
+    let panes: Vec<TmuxPane> = json.as_array().unwrap().iter().map(|p| TmuxPane::from_json(p).unwrap()).collect();
+
+    assert_eq!(panes[0], TmuxPane { written: Some(Value::from("htop")), ..TmuxPane::from_cmd("htop".to_string()) });
+    assert_eq!(panes[1].shell_command, Vec::<String>::new());
+    assert_eq!(panes[2].command(), "cd api; source .env; make run");
+    assert_eq!(panes[2].start_directory, Some(PathBuf::from("api")));
+    assert!(panes[3].focus);
+    assert_eq!((panes[3].sleep_before, panes[3].sleep_after, panes[3].enter), (Some(2.0), Some(0.5), Some(false)));
+    assert_eq!(TmuxPane::from_json(&serde_json::json!({"shell_command": "ls", "suppress_history": false})).unwrap()
+        .unknown_keys.get("suppress_history"), Some(&Value::Bool(false)));
+    assert!(TmuxPane::from_json(&serde_json::json!(3)).is_err());
+
+    // The panes are written the way they were loaded
+    let mut serializer = PaneSerializer::from_panes(panes, 3);
+    assert_eq!(serde_json::to_value(&serializer).unwrap(), json);
+    serializer.set_focus(2).unwrap();
+    serializer.set_pane_cmd(0, "btop".to_string()).unwrap();
+    assert_eq!(serde_json::to_value(&serializer).unwrap(), serde_json::json!([
+        "btop",
+        null,
+        {"shell_command": ["cd api", "source .env", "make run"], "start_directory": "api", "focus": "true"},
+        {"shell_command": "cargo watch -c", "sleep_before": 2, "sleep_after": 0.5, "enter": false}
+    ]));
+}
+
+#[test]
+fn test_set_panes_cmds_keeps_settings(){
+    let mut api = TmuxPane::from_cmd("make run".to_string());
+    api.start_directory = Some(PathBuf::from("api"));
+    let mut panes = PaneSerializer::from_panes(vec![TmuxPane::from_cmd("nvim".to_string()), api.clone()], 0);
+
+    panes.set_panes_cmds(&["make run".to_string(), "htop".to_string(), "nvim".to_string()]);
+    let got = panes.get_panes();
+    assert_eq!(got[0], TmuxPane { focus: true, ..api });
+    assert_eq!(got[1], TmuxPane::from_cmd("htop".to_string()));
+    assert_eq!(got[2], TmuxPane::from_cmd("nvim".to_string()));
+}
+
+#[test]
+fn test_hoist_common_steps(){
+    assert_eq!(command_steps("cd api&& echo 'a && b' && echo \\&& \"c&&d\" &&"),
+        vec!["cd api", "echo 'a && b'", "echo \\&& \"c&&d\""]);
+
+    let mut multi = TmuxPane::from_cmd("source venv/bin/activate".to_string());
+    multi.shell_command.extend(vec!["export RUST_LOG=debug".to_string(), "cargo run".to_string()]);
+    let mut panes = PaneSerializer::from_panes(vec![
+        TmuxPane::from_cmd("source venv/bin/activate && export RUST_LOG=debug && nvim".to_string()),
+        multi,
+        TmuxPane::from_cmd("source venv/bin/activate && export RUST_LOG=debug".to_string()),
+        TmuxPane::from_cmd("source venv/bin/activate&&export RUST_LOG=debug&&cargo test&&echo done".to_string()),
+    ], 1);
+
+    assert_eq!(panes.hoist_common_steps(), vec!["source venv/bin/activate", "export RUST_LOG=debug"]);
+    assert_eq!(panes.get_panes_cmds().unwrap(), vec!["nvim", "cargo run", "", "cargo test && echo done"]);
+    assert_eq!(panes.focused_index(), 1);
+    assert!(panes.hoist_common_steps().is_empty());
+
+    let mut single = PaneSerializer::from_cmds(vec!["a && b".to_string()], 0);
+    assert!(single.hoist_common_steps().is_empty());
+    assert_eq!(common_prefix(&[vec!["a".to_string()], vec![]]), Vec::<String>::new());
+}
+
+#[test]
+fn test_set_focus_keeps_order(){
+    let mut panes = PaneSerializer::from_cmds(vec!["a".to_string(), "b".to_string(), "c".to_string()], 0);
+    panes.set_focus(2).unwrap();
+    panes.set_focus(1).unwrap();
+    assert_eq!(panes.focused_index(), 1);
+    assert_eq!(panes.get_panes_cmds().unwrap(), vec!["a", "b", "c"]);
+    assert_eq!(serde_json::to_value(&panes).unwrap(), serde_json::json!(["a", {"shell_command": "b", "focus": "true"}, "c"]));
+    assert!(panes.set_focus(3).is_err());
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+enum PaneOp {
+    Insert(usize),
+    Remove(usize),
+    Move(usize, usize),
+    Swap(usize, usize),
+    SetCmd(usize),
+    Focus(usize),
+}
+
+#[cfg(test)]
+fn pane_op_strategy() -> impl proptest::strategy::Strategy<Value = PaneOp> {
+    use proptest::prelude::*;
+    // Indexes can be out of the window, the operation has to fail then
+    let index = 0..6usize;
+    prop_oneof![
+        index.clone().prop_map(PaneOp::Insert),
+        index.clone().prop_map(PaneOp::Remove),
+        (index.clone(), index.clone()).prop_map(|(a, b)| PaneOp::Move(a, b)),
+        (index.clone(), index.clone()).prop_map(|(a, b)| PaneOp::Swap(a, b)),
+        index.clone().prop_map(PaneOp::SetCmd),
+        index.prop_map(PaneOp::Focus),
+    ]
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_pane_operations_keep_focus(npanes in 1..4usize, focus in 0..4usize, ops in proptest::collection::vec(pane_op_strategy(), 0..20)) {
+        // Every command is unique, the focused pane is found back from its command
+        let mut cmds: Vec<String> = (0..npanes).map(|n| format!("cmd {}", n)).collect();
+        let mut focused = cmds[focus % npanes].clone();
+        let mut panes = PaneSerializer::from_cmds(cmds.clone(), focus % npanes);
+
+        for (n, op) in ops.into_iter().enumerate() {
+            let len = cmds.len();
+            let new_cmd = format!("new {}", n);
+            let valid = match op {
+                PaneOp::Insert(i) => i <= len,
+                PaneOp::Remove(i) => i < len && len > 1,
+                PaneOp::Move(a, b) | PaneOp::Swap(a, b) => a < len && b < len,
+                PaneOp::SetCmd(i) | PaneOp::Focus(i) => i < len,
+            };
+            let res = match op {
+                PaneOp::Insert(i) => panes.insert_pane(i, TmuxPane::from_cmd(new_cmd.clone())),
+                PaneOp::Remove(i) => panes.remove_pane(i),
+                PaneOp::Move(a, b) => panes.move_pane(a, b),
+                PaneOp::Swap(a, b) => panes.swap_panes(a, b),
+                PaneOp::SetCmd(i) => panes.set_pane_cmd(i, new_cmd.clone()),
+                PaneOp::Focus(i) => panes.set_focus(i),
+            };
+            proptest::prop_assert_eq!(res.is_ok(), valid, "{:?} on {} panes", op, len);
+            if !valid {
+                continue;
+            }
+
+            match op {
+                PaneOp::Insert(i) => cmds.insert(i, new_cmd),
+                PaneOp::Remove(i) => {
+                    let removed = cmds.remove(i);
+                    // The focus stays at the same position, or goes to the new last pane
+                    if removed == focused {
+                        focused = cmds[i.min(cmds.len() - 1)].clone();
+                    }
+                },
+                PaneOp::Move(a, b) => {
+                    let cmd = cmds.remove(a);
+                    cmds.insert(b, cmd);
+                },
+                PaneOp::Swap(a, b) => cmds.swap(a, b),
+                PaneOp::SetCmd(i) => {
+                    if cmds[i] == focused {
+                        focused = new_cmd.clone();
+                    }
+                    cmds[i] = new_cmd;
+                },
+                PaneOp::Focus(i) => focused = cmds[i].clone(),
+            }
+
+            let json = serde_json::to_value(&panes).unwrap();
+            let written = json.as_array().unwrap();
+            let focus_flags: Vec<usize> = written.iter().enumerate()
+                .filter(|(_, p)| p.get("focus") == Some(&Value::from("true")))
+                .map(|(n, _)| n)
+                .collect();
+            let expected_focus = cmds.iter().position(|c| *c == focused).unwrap();
+            proptest::prop_assert_eq!(focus_flags, vec![expected_focus]);
+            proptest::prop_assert_eq!(panes.focused_index(), expected_focus);
+            proptest::prop_assert_eq!(&panes.get_panes_cmds().unwrap(), &cmds);
+        }
+    }
+}
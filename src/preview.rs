@@ -0,0 +1,118 @@
+use crate::errors::Errcode;
+use crate::layout::{LayoutContent, LayoutNode, TermSize};
+use crate::window::TmuxWindow;
+
+const FOCUS_MARKER: &str = "*";
+
+/// Grid of characters the layout boxes are drawn on
+struct Canvas {
+    cells: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Canvas {
+        Canvas { cells: vec![vec![' '; width]; height] }
+    }
+
+    // Two different lines crossing each other make a corner
+    fn draw_line_char(&mut self, x: usize, y: usize, c: char) {
+        let cell = &mut self.cells[y][x];
+        *cell = if *cell == ' ' || *cell == c { c } else { '+' };
+    }
+
+    fn draw_box(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        for x in x0..=x1 {
+            self.draw_line_char(x, y0, '-');
+            self.draw_line_char(x, y1, '-');
+        }
+        for y in y0..=y1 {
+            self.draw_line_char(x0, y, '|');
+            self.draw_line_char(x1, y, '|');
+        }
+        for (x, y) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].iter() {
+            self.cells[*y][*x] = '+';
+        }
+    }
+
+    // Writes the text inside the box, wrapping it on the lines available
+    fn draw_text(&mut self, x: usize, y: usize, width: usize, height: usize, text: &str) {
+        let chars: Vec<char> = text.chars().collect();
+        for (n, line) in chars.chunks(width.max(1)).take(height).enumerate() {
+            for (i, c) in line.iter().enumerate() {
+                self.cells[y + n][x + i] = *c;
+            }
+        }
+    }
+
+    fn render(&self) -> String {
+        self.cells.iter()
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn panes_of(node: &LayoutNode) -> Vec<&LayoutNode> {
+    match &node.content {
+        LayoutContent::Pane(_) => vec![node],
+        LayoutContent::Split(_, children) => children.iter().flat_map(panes_of).collect(),
+    }
+}
+
+/// Draws the layout of the window in a box of the given size, labelling each pane
+/// with its index, the focus marker and its command
+pub fn render_window(win: &TmuxWindow, size: &TermSize) -> Result<String, Errcode> {
+    if size.width < 3 || size.height < 3 {
+        return Err(Errcode::LayoutError(format!("Cannot render a layout in {}", size)));
+    }
+
+    // The layout borders are drawn on the cells between the panes, only the outer frame needs space
+    let inner = TermSize { width: size.width - 2, height: size.height - 2 };
+    let mut layout = win.get_layout(&inner)?;
+    layout.resize(&inner)?;
+
+    let cmds = win.panes.get_panes_cmds()?;
+    let mut canvas = Canvas::new(size.width, size.height);
+    for (n, pane) in panes_of(&layout.root).iter().enumerate() {
+        canvas.draw_box(pane.x, pane.y, pane.x + pane.width + 1, pane.y + pane.height + 1);
+
+        let label = format!("{}{} {}",
+            n,
+            if n == win.panes.focused_index() { FOCUS_MARKER } else { "" },
+            cmds.get(n).map(|c| c.as_str()).unwrap_or("?"),
+        );
+        canvas.draw_text(pane.x + 1, pane.y + 1, pane.width, pane.height, &label);
+    }
+    Ok(canvas.render())
+}
+
+
+
+
+
+#[test]
+fn test_render_window(){
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use crate::layout::TmuxLayout;
+    use crate::pane::{TmuxPane, PaneSerializer};
+
+    let mut win = TmuxWindow::default(PathBuf::from("/tmp"));
+    win.layout = Some(TmuxLayout::from_str("dcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}").unwrap());
+    win.panes = PaneSerializer::create(TmuxPane::from_cmd("cargo watch -c".to_string()), 1,
+        vec![TmuxPane::from_cmd("nvim".to_string()), TmuxPane::from_cmd("htop".to_string())]);
+
+    let got = render_window(&win, &TermSize { width: 31, height: 9 }).unwrap();
+    let exp = [
+        "+--------------+--------------+",
+        "|0 nvim        |2 htop        |",
+        "|              |              |",
+        "|              |              |",
+        "+--------------+              |",
+        "|1* cargo watch|              |",
+        "| -c           |              |",
+        "|              |              |",
+        "+--------------+--------------+",
+    ].join("\n");
+    assert_eq!(got, exp);
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::serialisation::shell_quote;
+
+const PROC_ROOT: &str = "/proc";
+
+/// Programs never worth saving as a pane command
+pub const SHELLS: [&str; 7] = ["bash", "zsh", "sh", "fish", "dash", "ksh", "tcsh"];
+
+/// Reads the process tree from a procfs
+pub struct ProcFs {
+    root: PathBuf,
+}
+
+impl Default for ProcFs {
+    fn default() -> ProcFs {
+        ProcFs::new(PathBuf::from(PROC_ROOT))
+    }
+}
+
+impl ProcFs {
+    pub fn new(root: PathBuf) -> ProcFs {
+        ProcFs { root }
+    }
+
+    pub fn children(&self, pid: u32) -> Vec<u32> {
+        let pid_dir = self.root.join(pid.to_string());
+        // The main thread is the one running the shell, its task has the same id as the process
+        let content = fs::read_to_string(pid_dir.join("task").join(pid.to_string()).join("children"))
+            .or_else(|_| fs::read_to_string(pid_dir.join("children")))
+            .unwrap_or_default();
+        content.split_whitespace().filter_map(|p| u32::from_str(p).ok()).collect()
+    }
+
+    pub fn cmdline(&self, pid: u32) -> Option<Vec<String>> {
+        let raw = fs::read(self.root.join(pid.to_string()).join("cmdline")).ok()?;
+        let args: Vec<String> = raw.split(|b| *b == 0)
+            .filter(|a| !a.is_empty())
+            .map(|a| String::from_utf8_lossy(a).to_string())
+            .collect();
+        if args.is_empty() { None } else { Some(args) }
+    }
+
+    /// The command line of the program running in the foreground of a shell, or the shell itself
+    /// Goes through the shells started from the shell, down to the program they run
+    pub fn foreground_cmdline(&self, shell_pid: u32) -> Option<Vec<String>> {
+        let mut argv = self.cmdline(shell_pid)?;
+        let mut pid = shell_pid;
+        while SHELLS.contains(&program_name(&argv[0])) {
+            // The last child is the most recently started one
+            let child = match self.children(pid).last() {
+                Some(c) => *c,
+                None => break,
+            };
+            match self.cmdline(child) {
+                Some(a) => { argv = a; pid = child; },
+                None => break,
+            }
+        }
+        Some(argv)
+    }
+}
+
+/// Name of the program, without its path nor the "-" of login shells
+pub fn program_name(argv0: &str) -> &str {
+    let name = Path::new(argv0).file_name().and_then(|n| n.to_str()).unwrap_or(argv0);
+    name.trim_start_matches('-')
+}
+
+/// Which recovered commands are worth saving
+#[derive(Debug, Clone)]
+pub struct CommandFilter {
+    /// If not empty, only these programs are kept
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl Default for CommandFilter {
+    fn default() -> CommandFilter {
+        CommandFilter {
+            allow: vec![],
+            deny: SHELLS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl CommandFilter {
+    pub fn accepts(&self, argv: &[String]) -> bool {
+        let prog = match argv.first() {
+            Some(a) => program_name(a),
+            None => return false,
+        };
+        if self.deny.iter().any(|d| d == prog) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|a| a == prog)
+    }
+}
+
+/// Finds the command to save for each pane from its process tree
+pub struct CommandRecovery {
+    pub procfs: ProcFs,
+    pub filter: CommandFilter,
+}
+
+impl CommandRecovery {
+    /// The command running in the pane if it is worth saving, or blank if the pane only runs a shell
+    /// Uses the command name reported by tmux if the process tree cannot be read
+    pub fn pane_command(&self, pane_pid: u32, current_command: &str) -> String {
+        let argv = self.procfs.foreground_cmdline(pane_pid)
+            .unwrap_or_else(|| vec![current_command.to_string()]);
+        if self.filter.accepts(&argv) {
+            argv.iter().map(|a| shell_quote(a)).collect::<Vec<String>>().join(" ")
+        } else {
+            String::new()
+        }
+    }
+}
+
+
+
+
+
+#[test]
+fn test_command_recovery(){
+    let root = std::env::temp_dir().join(format!("tmuxp_session_creator_proc_{}", std::process::id()));
+    let add_proc = |pid: u32, cmdline: &str, children: &str| {
+        let task = root.join(pid.to_string()).join("task").join(pid.to_string());
+        fs::create_dir_all(&task).unwrap();
+        fs::write(root.join(pid.to_string()).join("cmdline"), cmdline).unwrap();
+        fs::write(task.join("children"), children).unwrap();
+    };
+    add_proc(100, "-bash\0", "101 ");
+    add_proc(101, "nvim\0src/main.rs\0", "");
+    add_proc(200, "/bin/zsh\0", "201");
+    add_proc(201, "-bash\0", "202");
+    add_proc(202, "htop\0--delay=10\0", "");
+    add_proc(300, "bash\0", "301 ");
+    add_proc(301, "/home/me/.cargo/bin/cargo\0watch\0-x\0test --all\0", "302");
+    add_proc(302, "cargo\0test\0", "");
+    add_proc(500, "fish\0", "");
+
+    let rec = CommandRecovery { procfs: ProcFs::new(root.clone()), filter: CommandFilter::default() };
+    assert_eq!(rec.pane_command(100, "bash"), "nvim src/main.rs");
+    assert_eq!(rec.pane_command(200, "zsh"), "htop --delay=10");
+    assert_eq!(rec.pane_command(201, "bash"), "htop --delay=10");
+    assert_eq!(rec.pane_command(300, "cargo"), "/home/me/.cargo/bin/cargo watch -x 'test --all'");
+    assert_eq!(rec.pane_command(400, "htop"), "htop");
+    assert_eq!(rec.pane_command(400, "bash"), "");
+    assert_eq!(rec.pane_command(500, "fish"), "");
+
+    let rec = CommandRecovery {
+        procfs: ProcFs::new(root.clone()),
+        filter: CommandFilter { allow: vec!["cargo".to_string()], deny: vec![] },
+    };
+    assert_eq!(rec.pane_command(100, "bash"), "");
+    assert_eq!(rec.pane_command(200, "zsh"), "");
+    assert_eq!(rec.pane_command(300, "cargo"), "/home/me/.cargo/bin/cargo watch -x 'test --all'");
+
+    fs::remove_dir_all(&root).unwrap();
+}
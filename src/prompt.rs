@@ -0,0 +1,239 @@
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use crate::errors::Errcode;
+
+const UNDO: &str = "u";
+const REDO: &str = "r";
+const LIST: &str = "l";
+const CONFIRM: &str = "y";
+const ABORT: &str = "n";
+// Allows to enter a command equal to one of the prompt keywords
+const ESCAPE: char = '\\';
+
+#[derive(Debug, Clone, PartialEq)]
+enum CmdEdit {
+    Drop(usize, String),
+    Add(String),
+}
+
+/// What the user is asked to do on the list of commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmdEditMode {
+    Drop(usize),
+    Add(usize),
+}
+
+/// List of commands edited by the user, keeping the whole history to undo / redo the changes
+pub struct CmdListEditor {
+    cmds: Vec<String>,
+    done: Vec<CmdEdit>,
+    undone: Vec<CmdEdit>,
+}
+
+impl CmdListEditor {
+    pub fn new(cmds: Vec<String>) -> CmdListEditor {
+        CmdListEditor { cmds, done: vec![], undone: vec![] }
+    }
+
+    fn apply(&mut self, edit: &CmdEdit) {
+        match edit {
+            CmdEdit::Drop(n, _) => { self.cmds.remove(*n); },
+            CmdEdit::Add(cmd) => self.cmds.push(cmd.clone()),
+        }
+    }
+
+    fn revert(&mut self, edit: &CmdEdit) {
+        match edit {
+            CmdEdit::Drop(n, cmd) => self.cmds.insert(*n, cmd.clone()),
+            CmdEdit::Add(_) => { self.cmds.pop(); },
+        }
+    }
+
+    fn push(&mut self, edit: CmdEdit) {
+        self.apply(&edit);
+        self.done.push(edit);
+        self.undone.clear();
+    }
+
+    pub fn drop(&mut self, index: usize) -> Result<(), Errcode> {
+        let cmd = self.cmds.get(index).cloned().ok_or_else(|| Errcode::ReconciliationError(
+            format!("No command {}, there are {} of them", index, self.cmds.len())
+        ))?;
+        self.push(CmdEdit::Drop(index, cmd));
+        Ok(())
+    }
+
+    pub fn add(&mut self, cmd: String) {
+        self.push(CmdEdit::Add(cmd));
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.done.pop() {
+            Some(edit) => {
+                self.revert(&edit);
+                self.undone.push(edit);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(edit) => {
+                self.apply(&edit);
+                self.done.push(edit);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn nb_edits(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn cmds(&self) -> &[String] {
+        &self.cmds
+    }
+
+    pub fn history(&self) -> Vec<String> {
+        self.done.iter().map(|e| match e {
+            CmdEdit::Drop(n, cmd) => format!("dropped command {}: \"{}\"", n, cmd),
+            CmdEdit::Add(cmd) => format!("added command \"{}\"", cmd),
+        }).collect()
+    }
+}
+
+fn print_cmds<W: Write>(output: &mut W, cmds: &[String]) -> Result<(), Errcode> {
+    writeln!(output, "\n\nCommands in panes: ")?;
+    for (n, c) in cmds.iter().enumerate() {
+        writeln!(output, "\t{}: {}", n, c)?;
+    }
+    Ok(())
+}
+
+fn read_answer<R: BufRead>(input: &mut R) -> Result<String, Errcode> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(Errcode::UserAborted);
+    }
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Asks a yes / no question until one of them is answered
+pub fn confirm<R: BufRead, W: Write>(question: &str, input: &mut R, output: &mut W) -> Result<bool, Errcode> {
+    loop {
+        writeln!(output, "{} ({}: yes, {}: no)", question, CONFIRM, ABORT)?;
+        match read_answer(input)?.trim() {
+            CONFIRM => return Ok(true),
+            ABORT => return Ok(false),
+            answer => writeln!(output, "Unknown answer \"{}\"", answer)?,
+        }
+    }
+}
+
+/// Asks the user to drop / add commands until the count is right, then to confirm the result
+/// The answers "u", "r" and "l" undo, redo and list the changes made
+pub fn edit_cmds<R: BufRead, W: Write>(cmds: Vec<String>, mode: CmdEditMode, input: &mut R, output: &mut W)
+    -> Result<Vec<String>, Errcode>
+{
+    let nedits = match mode {
+        CmdEditMode::Drop(n) | CmdEditMode::Add(n) => n,
+    };
+    let mut editor = CmdListEditor::new(cmds);
+    if let CmdEditMode::Add(n) = mode {
+        writeln!(output, "Asking for {} new commands", n)?;
+    }
+
+    loop {
+        print_cmds(output, editor.cmds())?;
+        let finished = editor.nb_edits() == nedits;
+        if finished {
+            writeln!(output, "Apply these commands? ({}: yes, {}: abort, {}: undo, {}: list changes)", CONFIRM, ABORT, UNDO, LIST)?;
+        } else {
+            match mode {
+                CmdEditMode::Drop(_) => writeln!(output, "Enter the number of the command to drop ({} left)", nedits - editor.nb_edits())?,
+                CmdEditMode::Add(_) => writeln!(output, "Enter a new command ({} left)", nedits - editor.nb_edits())?,
+            }
+            writeln!(output, "({}: undo, {}: redo, {}: list changes, prefix with {} to enter one of these as a command)", UNDO, REDO, LIST, ESCAPE)?;
+        }
+
+        let answer = read_answer(input)?;
+        match answer.as_str() {
+            UNDO => if !editor.undo() { writeln!(output, "Nothing to undo")?; },
+            REDO if !finished => if !editor.redo() { writeln!(output, "Nothing to redo")?; },
+            LIST => {
+                writeln!(output, "Changes made:")?;
+                for h in editor.history() {
+                    writeln!(output, "\t{}", h)?;
+                }
+            },
+            CONFIRM if finished => return Ok(editor.cmds().to_vec()),
+            ABORT if finished => return Err(Errcode::UserAborted),
+            _ if finished => writeln!(output, "Unknown answer \"{}\"", answer)?,
+
+            _ => match mode {
+                CmdEditMode::Drop(_) => match usize::from_str(answer.trim()).ok().filter(|n| *n < editor.cmds().len()) {
+                    Some(n) => {
+                        writeln!(output, "Dropping command \"{}\"", editor.cmds()[n])?;
+                        editor.drop(n)?;
+                    },
+                    None => writeln!(output, "Expected the number of a command, got \"{}\"", answer)?,
+                },
+                CmdEditMode::Add(_) => {
+                    let cmd = answer.strip_prefix(ESCAPE).unwrap_or(&answer).to_string();
+                    writeln!(output, "Added command \"{}\"", cmd)?;
+                    editor.add(cmd);
+                },
+            },
+        }
+    }
+}
+
+
+
+
+
+#[test]
+fn test_cmd_list_editor(){
+    let mut editor = CmdListEditor::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    editor.drop(1).unwrap();
+    editor.add("d".to_string());
+    assert_eq!(editor.cmds(), ["a", "c", "d"]);
+    assert!(editor.drop(3).is_err());
+
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo());
+    assert_eq!(editor.cmds(), ["a", "b", "c"]);
+
+    assert!(editor.redo());
+    assert_eq!(editor.cmds(), ["a", "c"]);
+    editor.drop(0).unwrap();
+    assert!(!editor.redo());
+    assert_eq!(editor.cmds(), ["c"]);
+    assert_eq!(editor.history(), ["dropped command 1: \"b\"", "dropped command 0: \"a\""]);
+}
+
+#[test]
+fn test_edit_cmds_prompt(){
+    let cmds = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let run = |mode, answers: &str| edit_cmds(cmds.clone(), mode, &mut answers.as_bytes(), &mut Vec::new());
+
+    assert_eq!(run(CmdEditMode::Drop(1), "0\ny\n").unwrap(), ["b", "c"]);
+    assert_eq!(run(CmdEditMode::Drop(1), "0\nu\n2\ny\n").unwrap(), ["a", "b"]);
+    assert_eq!(run(CmdEditMode::Drop(2), "9\nx\n0\nu\nr\nl\n0\ny\n").unwrap(), ["c"]);
+    assert_eq!(run(CmdEditMode::Add(2), "d\nu\ne\n\\u\ny\n").unwrap(), ["a", "b", "c", "e", "u"]);
+    assert!(matches!(run(CmdEditMode::Drop(1), "0\nn\n"), Err(Errcode::UserAborted)));
+    assert!(matches!(run(CmdEditMode::Add(1), "d\n"), Err(Errcode::UserAborted)));
+}
+
+#[test]
+fn test_confirm_prompt(){
+    let run = |answers: &str| confirm("Delete?", &mut answers.as_bytes(), &mut Vec::new());
+    assert!(run("y\n").unwrap());
+    assert!(!run("maybe\n\nn\n").unwrap());
+    assert!(matches!(run("x\n"), Err(Errcode::UserAborted)));
+}
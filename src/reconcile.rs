@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::errors::Errcode;
+
+/// What to do when a new layout doesn't have the same number of panes as the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtraPanesPolicy {
+    /// Ask the user which commands to drop / add
+    Prompt,
+    /// Drop the commands of the last panes
+    DropLast,
+    /// Drop the commands of the first panes
+    DropFirst,
+    /// Only drop the panes without any command, fails if there are not enough of them
+    KeepBlank,
+    /// Fail on any difference
+    Fail,
+}
+
+impl FromStr for ExtraPanesPolicy {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<ExtraPanesPolicy, Errcode> {
+        match s {
+            "prompt" => Ok(ExtraPanesPolicy::Prompt),
+            "drop-last" => Ok(ExtraPanesPolicy::DropLast),
+            "drop-first" => Ok(ExtraPanesPolicy::DropFirst),
+            "keep-blank" => Ok(ExtraPanesPolicy::KeepBlank),
+            "fail" => Ok(ExtraPanesPolicy::Fail),
+            _ => Err(Errcode::ArgValidationError(
+                "extra panes policy, expected prompt, drop-last, drop-first, keep-blank or fail"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneMappingEntry {
+    /// Keeps the command of this pane of the window
+    Pane(usize),
+    Command(String),
+    /// New pane, uses the new pane command
+    New,
+}
+
+/// For each pane of the new layout, where its command comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneMapping(pub Vec<PaneMappingEntry>);
+
+impl PaneMapping {
+    // Format:      JSON array with a pane index, a command or null for each pane     [0, 2, "htop", null]
+    pub fn from_json(json: &str) -> Result<PaneMapping, Errcode> {
+        let entries: Vec<Value> = serde_json::from_str(json)?;
+        let mapping = entries.iter().map(|e| match e {
+            Value::Number(n) => n.as_u64()
+                .map(|n| PaneMappingEntry::Pane(n as usize))
+                .ok_or_else(|| Errcode::JsonError(format!("Invalid pane index {} in pane mapping", n))),
+            Value::String(cmd) => Ok(PaneMappingEntry::Command(cmd.clone())),
+            Value::Null => Ok(PaneMappingEntry::New),
+            _ => Err(Errcode::JsonError(format!("Invalid pane mapping entry {}", e))),
+        }).collect::<Result<Vec<PaneMappingEntry>, Errcode>>()?;
+        Ok(PaneMapping(mapping))
+    }
+
+    pub fn load(path: &Path) -> Result<PaneMapping, Errcode> {
+        PaneMapping::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn apply(&self, cmds: &[String], npanes: usize, new_cmd: &str) -> Result<Vec<String>, Errcode> {
+        if self.0.len() != npanes {
+            return Err(Errcode::ReconciliationError(
+                format!("The pane mapping has {} entries, but the layout has {} panes", self.0.len(), npanes)
+            ));
+        }
+        self.0.iter().map(|e| match e {
+            PaneMappingEntry::Pane(n) => cmds.get(*n).cloned().ok_or_else(|| Errcode::ReconciliationError(
+                format!("The pane mapping uses pane {}, but the window only has {} panes", n, cmds.len())
+            )),
+            PaneMappingEntry::Command(c) => Ok(c.clone()),
+            PaneMappingEntry::New => Ok(new_cmd.to_string()),
+        }).collect()
+    }
+}
+
+/// How the commands of the panes are updated when the number of panes of a window changes
+#[derive(Debug, Clone)]
+pub struct Reconciliation {
+    pub policy: ExtraPanesPolicy,
+    pub new_pane_command: Option<String>,
+    pub interactive: bool,
+    pub mapping: Option<PaneMapping>,
+}
+
+impl Default for Reconciliation {
+    fn default() -> Reconciliation {
+        Reconciliation {
+            policy: ExtraPanesPolicy::Prompt,
+            new_pane_command: None,
+            interactive: true,
+            mapping: None,
+        }
+    }
+}
+
+impl Reconciliation {
+    fn new_cmd(&self) -> String {
+        self.new_pane_command.clone().unwrap_or_default()
+    }
+
+    fn mismatch(ncmds: usize, npanes: usize) -> Errcode {
+        Errcode::ReconciliationError(
+            format!("The layout has {} panes but the window has {} commands", npanes, ncmds)
+        )
+    }
+
+    /// Whether the user has to be asked for the commands to drop / add
+    pub fn needs_prompt(&self, ncmds: usize, npanes: usize) -> Result<bool, Errcode> {
+        if ncmds == npanes || self.mapping.is_some() {
+            return Ok(false);
+        }
+        if self.policy != ExtraPanesPolicy::Prompt || (npanes > ncmds && self.new_pane_command.is_some()) {
+            return Ok(false);
+        }
+        if !self.interactive {
+            return Err(Self::mismatch(ncmds, npanes));
+        }
+        Ok(true)
+    }
+
+    /// The commands of the panes of the new layout, without asking anything to the user
+    pub fn reconcile(&self, cmds: Vec<String>, npanes: usize) -> Result<Vec<String>, Errcode> {
+        if let Some(m) = &self.mapping {
+            return m.apply(&cmds, npanes, &self.new_cmd());
+        }
+        if cmds.len() == npanes {
+            return Ok(cmds);
+        }
+        if self.policy == ExtraPanesPolicy::Fail {
+            return Err(Self::mismatch(cmds.len(), npanes));
+        }
+
+        let mut cmds = cmds;
+        if cmds.len() < npanes {
+            cmds.resize(npanes, self.new_cmd());
+            return Ok(cmds);
+        }
+
+        let ndrop = cmds.len() - npanes;
+        match self.policy {
+            ExtraPanesPolicy::DropFirst => { cmds.drain(..ndrop); },
+            ExtraPanesPolicy::DropLast => cmds.truncate(npanes),
+            ExtraPanesPolicy::KeepBlank => {
+                let nblank = cmds.iter().filter(|c| c.trim().is_empty()).count();
+                if nblank < ndrop {
+                    return Err(Errcode::ReconciliationError(format!(
+                        "{} panes to drop, but only {} of them have no command", ndrop, nblank
+                    )));
+                }
+                // Drop the last blank panes first
+                let mut left = ndrop;
+                for i in (0..cmds.len()).rev() {
+                    if left > 0 && cmds[i].trim().is_empty() {
+                        cmds.remove(i);
+                        left -= 1;
+                    }
+                }
+            },
+            ExtraPanesPolicy::Prompt | ExtraPanesPolicy::Fail => return Err(Self::mismatch(cmds.len(), npanes)),
+        }
+        Ok(cmds)
+    }
+}
+
+
+
+
+
+#[test]
+fn test_reconciliation_policies(){
+    let cmds: Vec<String> = ["nvim", "", "cargo watch", ""].iter().map(|c| c.to_string()).collect();
+    let with_policy = |policy| Reconciliation { policy, interactive: false, ..Reconciliation::default() };
+
+    assert_eq!(with_policy(ExtraPanesPolicy::DropLast).reconcile(cmds.clone(), 2).unwrap(), vec!["nvim", ""]);
+    assert_eq!(with_policy(ExtraPanesPolicy::DropFirst).reconcile(cmds.clone(), 2).unwrap(), vec!["cargo watch", ""]);
+    assert_eq!(with_policy(ExtraPanesPolicy::KeepBlank).reconcile(cmds.clone(), 2).unwrap(), vec!["nvim", "cargo watch"]);
+    assert!(with_policy(ExtraPanesPolicy::KeepBlank).reconcile(cmds.clone(), 1).is_err());
+    assert!(with_policy(ExtraPanesPolicy::Fail).reconcile(cmds.clone(), 2).is_err());
+    assert_eq!(with_policy(ExtraPanesPolicy::Fail).reconcile(cmds.clone(), 4).unwrap(), cmds);
+
+    let mut rec = with_policy(ExtraPanesPolicy::DropLast);
+    assert_eq!(rec.reconcile(cmds.clone(), 5).unwrap().last().unwrap(), "");
+    rec.new_pane_command = Some("htop".to_string());
+    assert_eq!(rec.reconcile(cmds.clone(), 5).unwrap().last().unwrap(), "htop");
+}
+
+#[test]
+fn test_reconciliation_prompt(){
+    let rec = Reconciliation::default();
+    assert!(!rec.needs_prompt(2, 2).unwrap());
+    assert!(rec.needs_prompt(2, 3).unwrap());
+
+    let rec = Reconciliation { interactive: false, ..Reconciliation::default() };
+    assert!(rec.needs_prompt(2, 3).is_err());
+
+    let rec = Reconciliation { interactive: false, new_pane_command: Some("htop".to_string()), ..Reconciliation::default() };
+    assert!(!rec.needs_prompt(2, 3).unwrap());
+    assert!(rec.needs_prompt(3, 2).is_err());
+}
+
+#[test]
+fn test_pane_mapping(){
+    let cmds: Vec<String> = ["nvim", "cargo watch", "htop"].iter().map(|c| c.to_string()).collect();
+    let mapping = PaneMapping::from_json("[2, \"make run\", null, 0]").unwrap();
+    assert_eq!(mapping.apply(&cmds, 4, "bash").unwrap(), vec!["htop", "make run", "bash", "nvim"]);
+    assert!(mapping.apply(&cmds, 3, "").is_err());
+    assert!(PaneMapping::from_json("[5]").unwrap().apply(&cmds, 1, "").is_err());
+    assert!(PaneMapping::from_json("[true]").is_err());
+}
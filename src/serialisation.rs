@@ -1,4 +1,9 @@
-use serde_json::Value;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use std::path::Path;
+use std::str::FromStr;
 
 use crate::errors::Errcode;
 
@@ -8,3 +13,129 @@ pub fn strval_to_string(val: &Value) -> Result<String, Errcode> {
         .to_string().clone()
     )
 }
+
+/// Quotes a word for a POSIX shell, leaving it untouched when it doesn't need to be
+pub fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Booleans can be written as JSON booleans, or as "true" / "false" and "on" / "off" strings
+pub fn val_to_bool(val: &Value) -> Result<bool, Errcode> {
+    match val {
+        Value::Bool(b) => Ok(*b),
+        Value::String(s) if s == "true" || s == "on" => Ok(true),
+        Value::String(s) if s == "false" || s == "off" => Ok(false),
+        _ => Err(Errcode::JsonError(format!("Expected a boolean, got {}", val))),
+    }
+}
+
+/// Removes the key, keeping the order of the other ones
+/// (removing it from the map directly moves the last key in its place)
+pub fn remove_key(map: &mut Map<String, Value>, key: &str) -> Option<Value> {
+    let val = map.get(key).cloned()?;
+    *map = std::mem::take(map).into_iter().filter(|(k, _)| k != key).collect();
+    Some(val)
+}
+
+/// The known keys of a map, with the value written for each of them (if any), in the order they are written by default
+pub type Entries = Vec<(&'static str, Option<Value>)>;
+
+/// The entries to write for a map loaded from a file, `written` holds the map as it was written
+///
+/// The keys keep their order, and the values their form as long as `is_same` tells they hold the same data.
+/// A value that couldn't be loaded (`is_same` fails) is kept until its key gets a new value.
+/// Keys missing from the file are only added once they differ from `blank`, the values they were loaded with.
+pub fn written_entries<F>(entries: Entries, blank: Entries, unknown: &Map<String, Value>,
+    written: Option<&Map<String, Value>>, is_same: F) -> Vec<(String, Value)>
+where
+    F: Fn(&str, &Value) -> Result<bool, Errcode>,
+{
+    let written = match written {
+        Some(w) => w,
+        None => return entries.into_iter().filter_map(|(key, val)| Some((key.to_string(), val?)))
+            .chain(unknown.iter().map(|(key, val)| (key.clone(), val.clone())))
+            .collect(),
+    };
+
+    let mut res = vec![];
+    for (key, raw) in written.iter() {
+        let val = match entries.iter().position(|(k, _)| k == key) {
+            Some(n) => {
+                let (current, default) = (&entries[n].1, &blank[n].1);
+                let keep = current.as_ref() == Some(raw) || match is_same(key, raw) {
+                    Ok(same) => same,
+                    Err(_) => current == default,
+                };
+                if keep { Some(raw.clone()) } else { current.clone() }
+            },
+            None => unknown.get(key).cloned(),
+        };
+        if let Some(val) = val {
+            res.push((key.clone(), val));
+        }
+    }
+
+    for ((key, val), (_, default)) in entries.into_iter().zip(blank) {
+        match val {
+            Some(val) if !written.contains_key(key) && Some(&val) != default.as_ref() => res.push((key.to_string(), val)),
+            _ => {},
+        }
+    }
+    res.extend(unknown.iter().filter(|(key, _)| !written.contains_key(*key)).map(|(key, val)| (key.clone(), val.clone())));
+    res
+}
+
+/// Format of a session file, tmuxp reads both
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+}
+
+impl FileFormat {
+    pub const ALL: [FileFormat; 2] = [FileFormat::Json, FileFormat::Yaml];
+
+    /// The first one is used for the files written
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FileFormat::Json => &["json"],
+            FileFormat::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<FileFormat> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        FileFormat::ALL.iter().copied().find(|f| f.extensions().contains(&ext.as_str()))
+    }
+
+    pub fn serialize<T: Serialize>(self, val: &T) -> Result<String, Errcode> {
+        Ok(match self {
+            FileFormat::Json => serde_json::to_string_pretty(val)?,
+            FileFormat::Yaml => serde_yaml::to_string(val)?,
+        })
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T, Errcode> {
+        Ok(match self {
+            FileFormat::Json => serde_json::from_str(content)?,
+            FileFormat::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<FileFormat, Errcode> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(FileFormat::Json),
+            "yaml" | "yml" => Ok(FileFormat::Yaml),
+            _ => Err(Errcode::ArgValidationError("format, expected json or yaml")),
+        }
+    }
+}
@@ -1,24 +1,53 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::convert::TryFrom;
 use std::fs;
-use std::io::prelude::*;
 
-use serde::{Serializer, Serialize, Deserialize};
-use serde::ser::SerializeStruct;
-use serde_json::to_string_pretty;
+use serde::{Serializer, Serialize, Deserialize, Deserializer};
+use serde::ser::{self, SerializeMap};
+use serde::de::{self, Visitor, MapAccess};
+use serde_json::{Map, Value};
+
+use std::fmt;
 
 use dirs::home_dir;
 
 use crate::errors::Errcode;
+use crate::cli::CheckedArg;
 use crate::cli::create::TmuxpSessionCreation;
 
 use crate::window::TmuxWindow;
+use crate::layout::TermSize;
+use crate::options::{OptionScope, TmuxOptions};
+use crate::pane::{commands_from_json, common_prefix};
+use crate::reconcile::Reconciliation;
+use crate::serialisation::{remove_key, strval_to_string, val_to_bool, written_entries, Entries, FileFormat};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct TmuxSession {
     session_name: String,
     start_directory: PathBuf,
     pub windows: Vec<TmuxWindow>,
+
+    /// tmux socket the session is started on
+    pub socket_name: Option<String>,
+    /// Script run before the session is created
+    pub before_script: Option<String>,
+    /// Whether the commands sent to the panes are left out of the shell history
+    pub suppress_history: Option<bool>,
+    /// Commands run in every pane before its own commands
+    pub shell_command_before: Vec<String>,
+    pub options: TmuxOptions,
+    /// Options set for the whole tmux server
+    pub global_options: TmuxOptions,
+    /// Environment variables of the session
+    pub environment: Map<String, Value>,
+
+    // Keys this tool doesn't know about, written back as they were
+    unknown_keys: Map<String, Value>,
+    // The session as it is written in the file it was loaded from
+    written: Option<Map<String, Value>>,
+    /// Format of the file the session is written to
+    pub format: FileFormat,
 }
 
 impl TryFrom<&TmuxpSessionCreation> for TmuxSession {
@@ -28,12 +57,10 @@ impl TryFrom<&TmuxpSessionCreation> for TmuxSession {
 
         let startdir = c.start_directory.canonicalize()?;
         let mut windows : Vec<TmuxWindow> = {
-            println!("{:?}", c);
             if !c.default {
                 let mut res = vec![];
-                println!("{:?}", c.windows_description);
                 for windescr in c.windows_description.iter(){
-                    if windescr.len() > 0 {
+                    if !windescr.is_empty() {
                         res.push(TmuxWindow::try_from(windescr)?);
                     } else {
                         res.push(TmuxWindow::default(startdir.clone()));
@@ -49,11 +76,15 @@ impl TryFrom<&TmuxpSessionCreation> for TmuxSession {
             win.focus = n == c.focus;
         }
 
-        Ok(TmuxSession {
-            session_name: c.session_name.clone(),
-            start_directory: startdir.clone(),
-            windows,
-        })
+        let mut session = TmuxSession::new(c.session_name.clone(), startdir, windows);
+
+        let size = c.term_size.as_ref().map(|s| *s.get()).unwrap_or_else(TermSize::current);
+        for t in c.layout_template.iter().map(CheckedArg::get) {
+            let win = session.get_window_ref(t.window)?;
+            let layout = t.template.generate(&size, win.panes.nb_panes())?;
+            win.set_layout(&layout, &Reconciliation::default())?;
+        }
+        Ok(session)
     }
 }
 
@@ -62,54 +93,271 @@ impl Serialize for TmuxSession{
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("TmuxSession", 3)?;
-        state.serialize_field("session_name", &self.session_name)?;
-        state.serialize_field("start_directory", &self.start_directory)?;
-        state.serialize_field("windows", &self.windows)?;
+        let entries = written_entries(
+            self.entries().map_err(ser::Error::custom)?,
+            TmuxSession::blank().entries().map_err(ser::Error::custom)?,
+            &self.unknown_keys,
+            self.written.as_ref(),
+            |key, val| self.is_written_value(key, val),
+        );
+        let mut state = serializer.serialize_map(Some(entries.len()))?;
+        for (key, val) in entries.iter() {
+            state.serialize_entry(key, val)?;
+        }
         state.end()
     }
 }
 
-const TMUXP_DIR: &'static str = ".tmuxp/";
+const TMUXP_DIR: &str = ".tmuxp/";
 
 impl TmuxSession {
-    pub fn get_session_fname(name: &String) -> Result<PathBuf, Errcode> {
-        let mut output_fname = PathBuf::from(home_dir().ok_or(Errcode::EnvError(0))?);
+    pub fn new(session_name: String, start_directory: PathBuf, windows: Vec<TmuxWindow>) -> TmuxSession {
+        TmuxSession {
+            session_name,
+            start_directory,
+            windows,
+            socket_name: None,
+            before_script: None,
+            suppress_history: None,
+            shell_command_before: vec![],
+            options: TmuxOptions::new(OptionScope::Session),
+            global_options: TmuxOptions::new(OptionScope::Session),
+            environment: Map::new(),
+            unknown_keys: Map::new(),
+            written: None,
+            format: FileFormat::Json,
+        }
+    }
+
+    // The session the keys of a file are loaded into
+    fn blank() -> TmuxSession {
+        TmuxSession::new(String::new(), PathBuf::new(), vec![])
+    }
+
+    // The known keys with the value written for each of them
+    fn entries(&self) -> Result<Entries, Errcode> {
+        let cmds_before = if self.shell_command_before.is_empty() { None } else { Some(Value::from(self.shell_command_before.clone())) };
+        let environment = if self.environment.is_empty() { None } else { Some(Value::Object(self.environment.clone())) };
+        Ok(vec![
+            ("session_name", Some(Value::from(self.session_name.as_str()))),
+            ("start_directory", Some(serde_json::to_value(&self.start_directory)?)),
+            ("windows", Some(serde_json::to_value(&self.windows)?)),
+            ("socket_name", self.socket_name.as_deref().map(Value::from)),
+            ("before_script", self.before_script.as_deref().map(Value::from)),
+            ("suppress_history", self.suppress_history.map(Value::from)),
+            ("shell_command_before", cmds_before),
+            ("options", self.options.to_json()),
+            ("global_options", self.global_options.to_json()),
+            ("environment", environment),
+        ])
+    }
+
+    // Whether the value written in the file still holds the value of the key
+    fn is_written_value(&self, key: &str, val: &Value) -> Result<bool, Errcode> {
+        Ok(match key {
+            "session_name" => strval_to_string(val)? == self.session_name,
+            "start_directory" => Path::new(&strval_to_string(val)?) == self.start_directory,
+            "socket_name" => Some(strval_to_string(val)?) == self.socket_name,
+            "before_script" => Some(strval_to_string(val)?) == self.before_script,
+            "suppress_history" => Some(val_to_bool(val)?) == self.suppress_history,
+            "shell_command_before" => commands_from_json(val)? == self.shell_command_before,
+            // The windows, the options and the environment keep their own form
+            _ => false,
+        })
+    }
+
+    pub fn get_session_fname(name: &str, format: FileFormat) -> Result<PathBuf, Errcode> {
+        let mut output_fname = home_dir().ok_or(Errcode::HomeDirNotFound)?;
         output_fname.push(TMUXP_DIR);
         output_fname.push(name.replace(" ", "_"));
-        output_fname.set_extension("json");
+        output_fname.set_extension(format.extensions()[0]);
         Ok(output_fname)
     }
 
-    pub fn load(name: &String) -> Result<TmuxSession, Errcode> {
-        let fname = Self::get_session_fname(name)?;
-        let jsonses = fs::read_to_string(fname)?;
-        let res = serde_json::from_str(&jsonses)?;
+    /// The files of the profile that exist, whatever their format, JSON first
+    pub fn find_session_files(name: &str) -> Result<Vec<PathBuf>, Errcode> {
+        let mut found = vec![];
+        for format in FileFormat::ALL.iter() {
+            for ext in format.extensions() {
+                let fname = Self::get_session_fname(name, *format)?.with_extension(ext);
+                if fname.is_file() {
+                    found.push(fname);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// The names of the profiles stored in the tmuxp directory, sorted
+    pub fn list_profiles() -> Result<Vec<String>, Errcode> {
+        let dir = home_dir().ok_or(Errcode::HomeDirNotFound)?.join(TMUXP_DIR);
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && FileFormat::from_path(&path).is_some() {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+        // A profile can have a file in each format
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// The file the profile is loaded from
+    pub fn profile_file(name: &str) -> Result<PathBuf, Errcode> {
+        Self::find_session_files(name)?.into_iter().next()
+            .ok_or_else(|| Errcode::ProfileNotFound(name.to_string()))
+    }
+
+    pub fn profile_exists(name: &str) -> Result<bool, Errcode> {
+        Ok(!Self::find_session_files(name)?.is_empty())
+    }
+
+    /// Removes the files of the profile, in all the formats
+    pub fn delete_profile(name: &str) -> Result<Vec<PathBuf>, Errcode> {
+        let files = Self::find_session_files(name)?;
+        if files.is_empty() {
+            return Err(Errcode::ProfileNotFound(name.to_string()));
+        }
+        for fname in files.iter() {
+            fs::remove_file(fname)?;
+        }
+        Ok(files)
+    }
+
+    pub fn load(name: &str) -> Result<TmuxSession, Errcode> {
+        Self::load_file(&Self::profile_file(name)?)
+    }
+
+    /// Loads a session file from anywhere, files without a known extension are read as JSON
+    pub fn load_file(fname: &Path) -> Result<TmuxSession, Errcode> {
+        let format = FileFormat::from_path(fname).unwrap_or(FileFormat::Json);
+        let content = fs::read_to_string(fname).map_err(|e| Errcode::from(e).in_file(fname))?;
+        let mut res = TmuxSession::from_str(&content, format).map_err(|e| e.in_file(fname))?;
+        res.check_layouts(fname);
         Ok(res)
     }
 
-    pub fn write_to_file(&self) -> Result<(), Errcode> {
-        let output_fname = Self::get_session_fname(&self.session_name)?;
-        let mut file = fs::File::create(output_fname)?;
-        file.write_all(to_string_pretty(self)?.as_bytes())?;
+    pub fn from_str(content: &str, format: FileFormat) -> Result<TmuxSession, Errcode> {
+        let mut res: TmuxSession = format.deserialize(content)?;
+        res.format = format;
+        Ok(res)
+    }
+
+    /// The content of the session file, in the format of the session
+    pub fn to_file_content(&self) -> Result<String, Errcode> {
+        self.format.serialize(self)
+    }
+
+    // Tmux refuses layouts with a wrong checksum, fix them so the next write produces a valid file
+    fn check_layouts(&mut self, fname: &Path) {
+        for (n, win) in self.windows.iter_mut().enumerate() {
+            if let Some(layout) = win.layout.as_mut() {
+                if !layout.is_checksum_valid() {
+                    eprintln!("Warning: {}: invalid layout checksum for window {} (got {:04x}, expected {:04x}), will be regenerated",
+                        fname.display(), n, layout.checksum, layout.compute_checksum());
+                    layout.update_checksum();
+                }
+            }
+        }
+    }
+
+    /// Writes the profile in its format, its files in the other formats are removed
+    /// so the profile isn't loaded from an outdated file, returns the removed files
+    pub fn write_to_file(&self) -> Result<Vec<PathBuf>, Errcode> {
+        let output_fname = Self::get_session_fname(&self.session_name, self.format)?;
+        self.write_to(&output_fname)?;
+        let mut removed = vec![];
+        for fname in Self::find_session_files(&self.session_name)? {
+            if fname != output_fname {
+                fs::remove_file(&fname)?;
+                removed.push(fname);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Fails if the profile exists, unless `overwrite` is set
+    pub fn check_overwrite(name: &str, overwrite: bool) -> Result<(), Errcode> {
+        if !overwrite && Self::profile_exists(name)? {
+            return Err(Errcode::FileError(format!("The profile \"{}\" already exists, use -f to replace it", name)));
+        }
+        Ok(())
+    }
+
+    /// Writes the session as another profile, an existing profile is only replaced if `overwrite` is set,
+    /// returns the files of the profile removed as `write_to_file` does
+    pub fn write_as(&mut self, name: &str, overwrite: bool) -> Result<Vec<PathBuf>, Errcode> {
+        Self::check_overwrite(name, overwrite)?;
+        self.rename(name);
+        self.write_to_file()
+    }
+
+    pub fn write_to(&self, fname: &Path) -> Result<(), Errcode> {
+        let content = self.to_file_content()?;
+        fs::write(fname, content).map_err(|e| Errcode::from(e).in_file(fname))?;
         Ok(())
     }
 
     pub fn dump(&self) -> Result<(), Errcode> {
         println!();
-        println!("{}", to_string_pretty(self)?);
+        println!("{}", self.to_file_content()?.trim_end());
         Ok(())
     }
 
+    pub fn name(&self) -> &str {
+        &self.session_name
+    }
+
+    pub fn start_directory(&self) -> &Path {
+        &self.start_directory
+    }
+
+    /// The name of the session is also the name of its profile
+    pub fn rename(&mut self, name: &str) {
+        self.session_name = name.to_string();
+    }
+
     pub fn get_window_ref(&mut self, window_index: usize) -> Result<&mut TmuxWindow, Errcode> {
         let winlen = self.windows.len();
         self.windows.get_mut(window_index)
             .ok_or(Errcode::WindowNotFound(window_index, winlen))
     }
 
-    pub fn init_new_window(&mut self) -> Result<&mut TmuxWindow, Errcode> {
-        self.windows.push(TmuxWindow::default(self.start_directory.clone()));
-        Ok(self.windows.last_mut().unwrap())
+    pub fn set_environment(&mut self, name: &str, value: &str) {
+        self.environment.insert(name.to_string(), Value::from(value));
+    }
+
+    pub fn unset_environment(&mut self, name: &str) -> Result<(), Errcode> {
+        remove_key(&mut self.environment, name)
+            .ok_or_else(|| Errcode::OptionNotFound(name.to_string()))?;
+        Ok(())
+    }
+
+    /// Moves the first commands run before the panes of all the windows to the ones of the session
+    pub fn dedupe_windows(&mut self) -> Vec<String> {
+        if self.windows.len() < 2 {
+            return vec![];
+        }
+        let prefix = common_prefix(&self.windows.iter().map(|w| w.shell_command_before.clone()).collect::<Vec<Vec<String>>>());
+        for win in self.windows.iter_mut() {
+            win.shell_command_before.drain(..prefix.len());
+        }
+        self.shell_command_before.extend(prefix.iter().cloned());
+        prefix
+    }
+
+    pub fn resize_layouts(&mut self, size: &TermSize) -> Result<(), Errcode> {
+        for win in self.windows.iter_mut() {
+            win.resize_layout(size)?;
+        }
+        Ok(())
     }
 
     pub fn set_window_focus(&mut self, window_focus_ind: usize) -> Result<(), Errcode> {
@@ -124,4 +372,224 @@ impl TmuxSession {
 
         Ok(())
     }
+
+    fn check_window(&self, index: usize) -> Result<(), Errcode> {
+        if index >= self.windows.len() {
+            return Err(Errcode::WindowNotFound(index, self.windows.len()));
+        }
+        Ok(())
+    }
+
+    // Exactly one window is focused, the first one if none of them is
+    fn fix_window_focus(&mut self) {
+        let focused = self.windows.iter().position(|w| w.focus).unwrap_or(0);
+        for (n, win) in self.windows.iter_mut().enumerate() {
+            win.focus = n == focused;
+        }
+    }
+
+    /// Inserts a window at a position, at the end of the session if not set
+    pub fn add_window(&mut self, at: Option<usize>, win: TmuxWindow) -> Result<usize, Errcode> {
+        let at = at.unwrap_or(self.windows.len());
+        if at > self.windows.len() {
+            return Err(Errcode::WindowNotFound(at, self.windows.len()));
+        }
+        let focus = win.focus;
+        self.windows.insert(at, win);
+        if focus {
+            self.set_window_focus(at)?;
+        } else {
+            self.fix_window_focus();
+        }
+        Ok(at)
+    }
+
+    /// Removes a window, the focus goes to the window taking its place
+    pub fn remove_window(&mut self, index: usize) -> Result<TmuxWindow, Errcode> {
+        self.check_window(index)?;
+        if self.windows.len() == 1 {
+            return Err(Errcode::ArgValidationError("window, a session needs at least one window"));
+        }
+        let win = self.windows.remove(index);
+        if win.focus {
+            self.set_window_focus(index.min(self.windows.len() - 1))?;
+        } else {
+            self.fix_window_focus();
+        }
+        Ok(win)
+    }
+
+    // The windows keep their focus, the tmux indexes stay at their position
+    fn reorder_windows<F: FnOnce(&mut Vec<TmuxWindow>)>(&mut self, reorder: F) {
+        let indexes: Vec<Option<usize>> = self.windows.iter().map(|w| w.window_index).collect();
+        reorder(&mut self.windows);
+        for (win, index) in self.windows.iter_mut().zip(indexes) {
+            win.window_index = index;
+        }
+        self.fix_window_focus();
+    }
+
+    /// Moves a window to another position, the windows in between are shifted
+    pub fn move_window(&mut self, from: usize, to: usize) -> Result<(), Errcode> {
+        self.check_window(from)?;
+        self.check_window(to)?;
+        self.reorder_windows(|windows| {
+            let win = windows.remove(from);
+            windows.insert(to, win);
+        });
+        Ok(())
+    }
+
+    pub fn swap_windows(&mut self, a: usize, b: usize) -> Result<(), Errcode> {
+        self.check_window(a)?;
+        self.check_window(b)?;
+        self.reorder_windows(|windows| windows.swap(a, b));
+        Ok(())
+    }
+}
+
+
+impl<'de> Deserialize<'de> for TmuxSession {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>, {
+        deserializer.deserialize_map(TmuxSessionVisitor)
+    }
+}
+
+struct TmuxSessionVisitor;
+
+impl<'de> Visitor<'de> for TmuxSessionVisitor {
+    type Value = TmuxSession;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a map containing all the informations for a tmuxp session")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut session = TmuxSession::blank();
+        let mut written = Map::new();
+
+        while let Some((key, value)) = access.next_entry::<String, Value>()? {
+            written.insert(key.clone(), value.clone());
+            session.load_entry(key.clone(), value).map_err(|e| de::Error::custom(format!("\"{}\": {}", key, e.details())))?;
+        }
+
+        if !written.contains_key("session_name") {
+            return Err(de::Error::missing_field("session_name"));
+        }
+        if !written.contains_key("start_directory") {
+            return Err(de::Error::missing_field("start_directory"));
+        }
+        session.written = Some(written);
+        Ok(session)
+    }
+}
+
+trait TmuxSessionBuilder<V>{
+    fn load_entry(&mut self, key: String, value: V) -> Result<(), Errcode>;
+}
+
+impl TmuxSessionBuilder<Value> for TmuxSession{
+    fn load_entry(&mut self, key: String, value: Value) -> Result<(), Errcode> {
+        match key.as_ref() {
+            "session_name" => self.session_name = strval_to_string(&value)?,
+            "start_directory" => self.start_directory = PathBuf::from(strval_to_string(&value)?),
+            "windows" => self.windows = serde_json::from_value(value)?,
+
+            "socket_name" => self.socket_name = Some(strval_to_string(&value)?),
+            "before_script" => self.before_script = Some(strval_to_string(&value)?),
+            "suppress_history" => self.suppress_history = Some(val_to_bool(&value)?),
+            "shell_command_before" => self.shell_command_before = commands_from_json(&value)?,
+            "options" => self.options = TmuxOptions::from_json(OptionScope::Session, &value)?,
+            "global_options" => self.global_options = TmuxOptions::from_json(OptionScope::Session, &value)?,
+            "environment" => self.environment = value.as_object().cloned()
+                .ok_or_else(|| Errcode::JsonError("environment is not a map of variables".to_string()))?,
+
+            _ => { self.unknown_keys.insert(key, value); },
+        }
+        Ok(())
+    }
+}
+
+
+
+
+
+#[test]
+fn test_load_save_corpus(){
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus");
+    let mut nfiles = 0;
+    for entry in fs::read_dir(&corpus).unwrap() {
+        let path = entry.unwrap().path();
+        let content = fs::read_to_string(&path).unwrap();
+        let mut ses = TmuxSession::from_str(&content, FileFormat::Json).unwrap();
+        assert_eq!(ses.to_file_content().unwrap(), content, "{} changed after load / save", path.display());
+
+        ses.format = FileFormat::Yaml;
+        let mut ses = TmuxSession::from_str(&ses.to_file_content().unwrap(), FileFormat::Yaml).unwrap();
+        ses.format = FileFormat::Json;
+        assert_eq!(ses.to_file_content().unwrap(), content, "{} changed after a YAML conversion", path.display());
+        nfiles += 1;
+    }
+    assert!(nfiles > 0);
+}
+
+#[test]
+fn test_window_operations(){
+    let named = |name: &str| {
+        let mut win = TmuxWindow::default(PathBuf::from("/tmp"));
+        win.window_name = name.to_string();
+        win
+    };
+    let names = |ses: &TmuxSession| ses.windows.iter().map(|w| w.window_name.clone()).collect::<Vec<String>>();
+    let focused = |ses: &TmuxSession| ses.windows.iter().enumerate().filter(|(_, w)| w.focus).map(|(n, _)| n).collect::<Vec<usize>>();
+
+    let mut ses = TmuxSession::new("s".to_string(), PathBuf::from("/tmp"), vec![named("a")]);
+    assert_eq!(ses.add_window(None, named("b")).unwrap(), 1);
+    assert_eq!(focused(&ses), vec![0]);
+    let mut c = named("c");
+    c.focus = true;
+    assert_eq!(ses.add_window(Some(1), c).unwrap(), 1);
+    assert_eq!((names(&ses), focused(&ses)), (vec!["a".to_string(), "c".to_string(), "b".to_string()], vec![1]));
+    assert!(matches!(ses.add_window(Some(4), named("d")), Err(Errcode::WindowNotFound(4, 3))));
+
+    ses.windows[2].window_index = Some(5);
+    ses.move_window(1, 2).unwrap();
+    assert_eq!((names(&ses), focused(&ses)), (vec!["a".to_string(), "b".to_string(), "c".to_string()], vec![2]));
+    assert_eq!(ses.windows.iter().map(|w| w.window_index).collect::<Vec<Option<usize>>>(), vec![None, None, Some(5)]);
+    ses.swap_windows(0, 2).unwrap();
+    assert_eq!((names(&ses), focused(&ses)), (vec!["c".to_string(), "b".to_string(), "a".to_string()], vec![0]));
+    assert!(matches!(ses.swap_windows(0, 3), Err(Errcode::WindowNotFound(3, 3))));
+    assert!(matches!(ses.move_window(3, 0), Err(Errcode::WindowNotFound(3, 3))));
+
+    assert_eq!(ses.remove_window(0).unwrap().window_name, "c");
+    assert_eq!((names(&ses), focused(&ses)), (vec!["b".to_string(), "a".to_string()], vec![0]));
+    ses.remove_window(1).unwrap();
+    assert!(matches!(ses.remove_window(1), Err(Errcode::WindowNotFound(1, 1))));
+    assert!(ses.remove_window(0).is_err());
+    assert_eq!((names(&ses), focused(&ses)), (vec!["b".to_string()], vec![0]));
+}
+
+#[test]
+fn test_edit_keeps_written_form(){
+    let json = r#"{"session_name":"t","start_directory":"/tmp","windows":[{"window_name":"editor","layout":"main-vertical","focus":true,"panes":[{"shell_command":["ls"],"focus":true},null,"htop"]},{"focus":"yes","window_name":"logs","panes":["tail -f app.log"]}]}"#;
+    let mut ses = TmuxSession::from_str(json, FileFormat::Json).unwrap();
+    ses.get_window_ref(0).unwrap().window_name = "editor2".to_string();
+    assert_eq!(serde_json::to_string(&ses).unwrap(), json.replace("editor", "editor2"));
+
+    // Only the values that changed take the form of the tool, the new keys are added at the end
+    let win = ses.get_window_ref(0).unwrap();
+    win.panes.set_focus(2).unwrap();
+    win.options.set("synchronize-panes", "yes").unwrap();
+    let ses_json: Value = serde_json::from_str(&serde_json::to_string(&ses).unwrap()).unwrap();
+    assert_eq!(serde_json::to_string(&ses_json["windows"][0]).unwrap(),
+        r#"{"window_name":"editor2","layout":"main-vertical","focus":true,"panes":[{"shell_command":["ls"]},null,{"shell_command":"htop","focus":"true"}],"options":{"synchronize-panes":"on"}}"#);
+    // The invalid focus couldn't be loaded, it is kept until the window gets focused
+    assert_eq!(ses_json["windows"][1]["focus"], "yes");
+    ses.set_window_focus(1).unwrap();
+    let ses_json: Value = serde_json::from_str(&serde_json::to_string(&ses).unwrap()).unwrap();
+    assert_eq!((&ses_json["windows"][0].get("focus"), &ses_json["windows"][1]["focus"]), (&None, &Value::from("true")));
 }
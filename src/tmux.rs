@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::errors::Errcode;
+use crate::layout::TmuxLayout;
+use crate::pane::{PaneSerializer, TmuxPane};
+use crate::procs::CommandRecovery;
+use crate::session::TmuxSession;
+use crate::window::TmuxWindow;
+
+// Fields of the tmux formats are separated by tabs, the last field can contain anything
+const FORMAT_SEP: &str = "\t";
+const WINDOW_FORMAT: &str = "#{window_index}\t#{window_active}\t#{automatic-rename}\t#{window_layout}\t#{window_name}";
+const PANE_FORMAT: &str = "#{window_index}\t#{pane_index}\t#{pane_active}\t#{pane_pid}\t#{pane_current_command}\t#{pane_current_path}";
+
+/// A window of a running tmux session
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub index: usize,
+    pub active: bool,
+    pub automatic_rename: bool,
+    pub layout: String,
+    pub name: String,
+}
+
+/// A pane of a running tmux session
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneInfo {
+    pub window_index: usize,
+    pub index: usize,
+    pub active: bool,
+    pub pid: u32,
+    pub current_command: String,
+    pub current_path: PathBuf,
+}
+
+fn tmux_flag(s: &str) -> bool {
+    s == "1" || s == "on"
+}
+
+fn format_fields(line: &str, nfields: usize) -> Result<Vec<&str>, Errcode> {
+    let fields: Vec<&str> = line.splitn(nfields, FORMAT_SEP).collect();
+    if fields.len() != nfields {
+        return Err(Errcode::TmuxError(format!("Unexpected output from tmux: \"{}\"", line)));
+    }
+    Ok(fields)
+}
+
+impl FromStr for WindowInfo {
+    type Err = Errcode;
+
+    fn from_str(line: &str) -> Result<WindowInfo, Errcode> {
+        let f = format_fields(line, 5)?;
+        Ok(WindowInfo {
+            index: usize::from_str(f[0])?,
+            active: tmux_flag(f[1]),
+            automatic_rename: tmux_flag(f[2]),
+            layout: f[3].to_string(),
+            name: f[4].to_string(),
+        })
+    }
+}
+
+impl FromStr for PaneInfo {
+    type Err = Errcode;
+
+    fn from_str(line: &str) -> Result<PaneInfo, Errcode> {
+        let f = format_fields(line, 6)?;
+        Ok(PaneInfo {
+            window_index: usize::from_str(f[0])?,
+            index: usize::from_str(f[1])?,
+            active: tmux_flag(f[2]),
+            pid: u32::from_str(f[3])?,
+            current_command: f[4].to_string(),
+            current_path: PathBuf::from(f[5]),
+        })
+    }
+}
+
+impl PaneInfo {
+    /// The pane to save, starting in its own directory if the window starts elsewhere
+    pub fn pane(&self, window_dir: &Path, recovery: &CommandRecovery) -> TmuxPane {
+        let mut pane = TmuxPane::from_cmd(recovery.pane_command(self.pid, &self.current_command));
+        if self.current_path != window_dir {
+            pane.start_directory = Some(self.current_path.clone());
+        }
+        pane
+    }
+}
+
+/// Queries a running tmux server
+pub struct TmuxClient {
+    executable: PathBuf,
+}
+
+impl TmuxClient {
+    pub fn new(executable: PathBuf) -> TmuxClient {
+        TmuxClient { executable }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, Errcode> {
+        let output = Command::new(&self.executable).args(args).output()
+            .map_err(|e| Errcode::TmuxError(format!("Unable to run {}: {}", self.executable.display(), e)))?;
+        if !output.status.success() {
+            return Err(Errcode::TmuxError(format!("tmux {} failed: {}",
+                args.join(" "), String::from_utf8_lossy(&output.stderr).trim())));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| Errcode::TmuxError("tmux output is not valid UTF-8".to_string()))
+    }
+
+    // "=" makes tmux look for a session with exactly this name
+    fn target(session: &str) -> String {
+        format!("={}", session)
+    }
+
+    pub fn current_session(&self) -> Result<String, Errcode> {
+        Ok(self.run(&["display-message", "-p", "#S"])?.trim_end().to_string())
+    }
+
+    pub fn session_path(&self, session: &str) -> Result<PathBuf, Errcode> {
+        // display-message expects a pane, the trailing ":" targets the current pane of the session
+        let target = format!("{}:", Self::target(session));
+        let out = self.run(&["display-message", "-p", "-t", &target, "#{session_path}"])?;
+        Ok(PathBuf::from(out.trim_end()))
+    }
+
+    pub fn list_windows(&self, session: &str) -> Result<Vec<WindowInfo>, Errcode> {
+        self.run(&["list-windows", "-t", &Self::target(session), "-F", WINDOW_FORMAT])?
+            .lines().map(WindowInfo::from_str).collect()
+    }
+
+    pub fn list_panes(&self, session: &str) -> Result<Vec<PaneInfo>, Errcode> {
+        self.run(&["list-panes", "-s", "-t", &Self::target(session), "-F", PANE_FORMAT])?
+            .lines().map(PaneInfo::from_str).collect()
+    }
+
+    /// Builds the tmuxp session reproducing the running tmux session
+    pub fn capture_session(&self, session: &str, profile_name: &str, recovery: &CommandRecovery)
+        -> Result<TmuxSession, Errcode>
+    {
+        let mut start_directory = self.session_path(session)?;
+        let panes = self.list_panes(session)?;
+
+        let mut windows = vec![];
+        for info in self.list_windows(session)? {
+            let mut win_panes: Vec<&PaneInfo> = panes.iter().filter(|p| p.window_index == info.index).collect();
+            win_panes.sort_by_key(|p| p.index);
+            if win_panes.is_empty() {
+                return Err(Errcode::TmuxError(format!("No pane found for window {}", info.index)));
+            }
+
+            let window_dir = win_panes[0].current_path.clone();
+            let mut win = TmuxWindow::default(window_dir.clone());
+            win.window_name = info.name.clone();
+            win.window_index = Some(info.index);
+            win.focus = info.active;
+            win.set_automatic_rename(info.automatic_rename);
+            win.layout = Some(TmuxLayout::from_str(&info.layout)?);
+            win.panes = PaneSerializer::from_panes(
+                win_panes.iter().map(|p| p.pane(&window_dir, recovery)).collect(),
+                win_panes.iter().position(|p| p.active).unwrap_or(0),
+            );
+            windows.push(win);
+        }
+
+        if start_directory.as_os_str().is_empty() {
+            start_directory = windows[0].start_directory.clone().unwrap_or_default();
+        }
+        Ok(TmuxSession::new(profile_name.to_string(), start_directory, windows))
+    }
+}
+
+
+
+
+
+#[cfg(unix)]
+#[test]
+fn test_capture_session(){
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use crate::procs::{CommandFilter, ProcFs};
+
+    let stub = std::env::temp_dir().join(format!("tmuxp_session_creator_stub_{}", std::process::id()));
+    fs::write(&stub, r#"#!/bin/sh
+case "$1" in
+    display-message) echo "/home/me/proj" ;;
+    list-windows) printf '1\t0\t1\t5be4,211x62,0,0,15\tbash\n2\t1\t0\tdcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}\tcode: main\n' ;;
+    list-panes) printf '1\t0\t1\t100\tbash\t/home/me/proj\n2\t0\t0\t101\tnvim\t/home/me/proj/src\n2\t2\t0\t103\tbash\t/home/me/proj/src\n2\t1\t1\t102\tcargo\t/home/me/proj\n' ;;
+    *) exit 1 ;;
+esac
+"#).unwrap();
+    fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // No process tree available, only the commands reported by tmux are used
+    let recovery = CommandRecovery {
+        procfs: ProcFs::new(std::env::temp_dir().join("tmuxp_session_creator_no_proc")),
+        filter: CommandFilter::default(),
+    };
+    let client = TmuxClient::new(stub.clone());
+    let mut ses = client.capture_session("proj", "proj-profile", &recovery).unwrap();
+    fs::remove_file(&stub).unwrap();
+
+    assert_eq!(ses.windows.len(), 2);
+    let win = ses.get_window_ref(1).unwrap();
+    assert_eq!((win.window_name.as_str(), win.window_index, win.focus), ("code: main", Some(2), true));
+    assert_eq!(win.start_directory, Some(PathBuf::from("/home/me/proj/src")));
+    assert_eq!(win.layout.as_ref().unwrap().nb_panes(), 3);
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["nvim", "cargo", ""]);
+    let start_dirs: Vec<Option<PathBuf>> = win.panes.get_panes().into_iter().map(|p| p.start_directory).collect();
+    assert_eq!(start_dirs, vec![None, Some(PathBuf::from("/home/me/proj")), None]);
+    assert_eq!(win.panes.focused_index(), 1);
+
+    assert!(!ses.get_window_ref(0).unwrap().focus);
+    assert!(TmuxClient::new(PathBuf::from("/nonexistent/tmux")).capture_session("proj", "proj", &recovery).is_err());
+}
@@ -1,37 +1,53 @@
 use serde::{Serializer, Serialize, Deserialize, Deserializer};
-use serde::ser::SerializeStruct;
+use serde::ser::{self, SerializeMap};
 use serde::de::{Visitor, MapAccess};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use std::fmt;
+use std::io;
 use std::str::FromStr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::cmp::Ordering;
 use std::convert::TryFrom;
 
-use text_io::{read, scan};
-
 use nom::IResult;
 use nom::bytes::complete::take_until;
 
 
 use crate::errors::Errcode;
-use crate::pane::{PaneSerializer, FocusedPane};
-use crate::serialisation::strval_to_string;
+use crate::layout::{LayoutTemplate, SplitDirection, TermSize, TmuxLayout};
+use crate::layout::preset::LayoutPreset;
+use crate::options::{OptionScope, TmuxOptions};
+use crate::pane::{commands_from_json, PaneSerializer, TmuxPane};
+use crate::reconcile::Reconciliation;
+use crate::prompt::{edit_cmds, CmdEditMode};
+use crate::serialisation::{strval_to_string, val_to_bool, written_entries, Entries};
 
 pub type WindowDescription = String;
 
 #[derive(Debug)]
 pub struct TmuxWindow {
     pub window_name: String,
-    pub layout: Option<String>,
+    pub window_index: Option<usize>,
+    pub layout: Option<TmuxLayout>,
+    /// tmux preset applied to the panes, used when there is no layout string
+    pub layout_preset: Option<LayoutPreset>,
     pub focus: bool,
-    pub start_directory: PathBuf,
+    /// Relative to the one of the session, the session's one if not set
+    pub start_directory: Option<PathBuf>,
 
     pub panes: PaneSerializer,
 
-    // options
-    automatic_rename: bool,
+    pub options: TmuxOptions,
+    /// Options set once the panes are created
+    pub options_after: TmuxOptions,
+    /// Commands run in every pane before its own commands
+    pub shell_command_before: Vec<String>,
+
+    // Keys this tool doesn't know about, written back as they were
+    unknown_keys: Map<String, Value>,
+    // The window as it is written in the file it was loaded from
+    written: Option<Map<String, Value>>,
 }
 
 impl TryFrom<&WindowDescription> for TmuxWindow {
@@ -44,15 +60,17 @@ impl TryFrom<&WindowDescription> for TmuxWindow {
     }
 }
 
-impl Into<WindowDescription> for TmuxWindow {
+impl From<TmuxWindow> for WindowDescription {
     // TODO Parse WindowDescription
-    fn into(self) -> WindowDescription {
+    fn from(_win: TmuxWindow) -> WindowDescription {
         String::from("")
     }
 }
 
-const LAYOUT_DESCR_TAG: &'static str = "#";
-const WINDOWDESCR_PARSER_SEP: &'static str = ":";
+const LAYOUT_DESCR_TAG: &str = "#";
+pub const DEFAULT_PANE_CMD: &str = "clear && bash";
+pub const AUTOMATIC_RENAME: &str = "automatic-rename";
+const WINDOWDESCR_PARSER_SEP: &str = ":";
 
 fn until_sep(s: &str) -> IResult<&str, &str> {
     assert!(s.len() >= WINDOWDESCR_PARSER_SEP.len());
@@ -70,88 +88,139 @@ fn until_sep(s: &str) -> IResult<&str, &str> {
 
 impl TmuxWindow {
     pub fn default(start_directory: PathBuf) -> TmuxWindow {
-        TmuxWindow {
+        let mut win = TmuxWindow {
             window_name: String::from("bash"),
+            window_index: None,
             layout: None,
+            layout_preset: None,
             focus: false,
             panes: PaneSerializer::create(
-                FocusedPane::from_cmd("clear && bash".to_string()), 0,
+                TmuxPane::from_cmd(DEFAULT_PANE_CMD.to_string()), 0,
                 vec![]),
-            automatic_rename: true,
-            start_directory
+            options: TmuxOptions::new(OptionScope::Window),
+            options_after: TmuxOptions::new(OptionScope::Window),
+            shell_command_before: vec![],
+            unknown_keys: Map::new(),
+            written: None,
+            start_directory: Some(start_directory),
+        };
+        win.set_automatic_rename(true);
+        win
+    }
+
+    // The window the keys of a file are loaded into
+    fn blank() -> TmuxWindow {
+        let mut win = TmuxWindow::default(PathBuf::new());
+        win.start_directory = None;
+        win.options = TmuxOptions::new(OptionScope::Window);
+        win
+    }
+
+    /// The directory the window starts in, a relative one is relative to the one of the session
+    pub fn directory(&self, session_dir: &Path) -> PathBuf {
+        match &self.start_directory {
+            Some(dir) => session_dir.join(dir),
+            None => session_dir.to_path_buf(),
         }
     }
 
-    fn load_json_options(&mut self, value: Value) -> Result<(), Errcode> {
-        if let Value::Object(opt) = value {
-            for (key, val) in opt.iter(){
-                match key.as_ref() {
-                    "automatic-rename" => self.automatic_rename = val.to_string() == "on",
-                    _ => return Err(Errcode::OptionNotFound(val.to_string())),
-                }
-            }
-        } else {
-            return Err(Errcode::JsonError("Json loading options".to_string()))
+    pub fn set_automatic_rename(&mut self, automatic_rename: bool) {
+        self.options.set_flag(AUTOMATIC_RENAME, automatic_rename);
+    }
+
+    // Format:      tmux layout string  |  name of a tmux preset
+    fn load_layout(&mut self, layout: &str) -> Result<(), Errcode> {
+        match LayoutPreset::from_str(layout) {
+            Ok(preset) => self.layout_preset = Some(preset),
+            Err(_) => self.layout = Some(TmuxLayout::from_str(layout)?),
         }
         Ok(())
     }
 
     fn load_json_panes(&mut self, value: Value) -> Result<(), Errcode> {
-        let mut nfoc = 0;
-        let mut panes_cmd = vec![];
-        let mut foc: Option<FocusedPane> = None;
-
-        if let Value::Array(panes) = value {
-            for (nb, pane) in panes.iter().enumerate() {
-                match pane {
-                    Value::String(cmd) => panes_cmd.push(cmd.clone()),
-                    Value::Object(val) => {
-                        nfoc = nb;
-                        foc = Some(FocusedPane::from_json(val)?)
-                    },
-                    _ => return Err(Errcode::JsonError("Json loading panes inner values".to_string())),
-
-                }
-            }
+        let panes = if let Value::Array(panes) = value {
+            panes.iter().map(TmuxPane::from_json).collect::<Result<Vec<TmuxPane>, Errcode>>()?
         } else {
             return Err(Errcode::JsonError("Json loading panes".to_string()))
-        }
-        
-        if let Some(f) = foc {
-            self.panes = PaneSerializer::create(f, nfoc, panes_cmd);
-        } else {
+        };
+        if panes.is_empty() {
             return Err(Errcode::JsonError("Missing data for pane loading from Json".to_string()));
         }
-
+        self.panes = PaneSerializer::from_loaded(panes);
         Ok(())
     }
 
+    // The known keys with the value written for each of them
+    fn entries(&self) -> Result<Entries, Errcode> {
+        let layout = match (&self.layout, self.layout_preset) {
+            (Some(layout), _) => Some(layout.to_string()),
+            (None, preset) => preset.map(|p| p.to_string()),
+        };
+        let cmds_before = if self.shell_command_before.is_empty() { None } else { Some(Value::from(self.shell_command_before.clone())) };
+        Ok(vec![
+            ("window_name", Some(Value::from(self.window_name.as_str()))),
+            ("window_index", self.window_index.map(Value::from)),
+            ("layout", layout.map(Value::from)),
+            ("start_directory", self.start_directory.as_ref().map(|d| serde_json::json!(d.to_str()))),
+            ("focus", if self.focus { Some(Value::from("true")) } else { None }),
+            ("panes", Some(serde_json::to_value(&self.panes)?)),
+            ("options", self.options.to_json()),
+            ("options_after", self.options_after.to_json()),
+            ("shell_command_before", cmds_before),
+        ])
+    }
+
+    // Whether the value written in the file still holds the value of the key, fails if it cannot be loaded
+    fn is_written_value(&self, key: &str, val: &Value) -> Result<bool, Errcode> {
+        Ok(match key {
+            "window_name" => strval_to_string(val)? == self.window_name,
+            "window_index" => Some(index_from_json(val)?) == self.window_index,
+            "layout" => {
+                let mut loaded = TmuxWindow::blank();
+                loaded.load_layout(&strval_to_string(val)?)?;
+                loaded.layout == self.layout && loaded.layout_preset == self.layout_preset
+            },
+            "focus" => val_to_bool(val)? == self.focus,
+            "start_directory" => Some(Path::new(&strval_to_string(val)?)) == self.start_directory.as_deref(),
+            "shell_command_before" => commands_from_json(val)? == self.shell_command_before,
+            // The panes and the options keep their own form
+            "panes" => {
+                TmuxWindow::blank().load_json_panes(val.clone())?;
+                false
+            },
+            "options" | "options_after" if !val.is_object() => {
+                return Err(Errcode::JsonError("Json loading options".to_string()));
+            },
+            _ => false,
+        })
+    }
+
     // Format:      NAME:STARTDIR:AUTORENAME:FOCUSED_PANE:PANE0:<PANE1>:<etc...>
     fn parse_windescr(&mut self, input: &str) -> Result<(), Errcode> {
         let (input, window_name) = until_sep(input)?;
         self.window_name = window_name.to_string();
         
         let (input, startdir) = until_sep(input)?;
-        self.start_directory = PathBuf::from_str(startdir)
+        self.start_directory = Some(PathBuf::from_str(startdir)
             .or(Err(
                 Errcode::ParsingError("Failed to get path from window description".to_string())
                 ))?
-            .canonicalize()?;
+            .canonicalize()?);
 
         let (input, autorename_str) = until_sep(input)?;
-        self.automatic_rename = autorename_str == "on";
+        self.set_automatic_rename(autorename_str == "on");
         
         let (input, focused_str) = until_sep(input)?;
         let pane_focused = usize::from_str(focused_str)?;
         
         let mut panes_cmd = vec![];
         let mut input = input;
-        while input.len() > 0 {
+        while !input.is_empty() {
             let (new_input, panecmd) = until_sep(input)?;
-            let cmd = if new_input.len() == 0 {
+            let cmd = if new_input.is_empty() {
                 if panecmd.contains(LAYOUT_DESCR_TAG){
                     let (layout_str, lastcmd) = take_until(LAYOUT_DESCR_TAG)(panecmd)?;
-                    self.layout = Some(layout_str.to_string());
+                    self.layout = Some(TmuxLayout::from_str(&layout_str[LAYOUT_DESCR_TAG.len()..])?);
                     lastcmd
                 } else {
                     panecmd
@@ -163,12 +232,10 @@ impl TmuxWindow {
             input = new_input;
         }
 
-        self.panes = PaneSerializer::create(
-            FocusedPane::from_cmd(panes_cmd[pane_focused].clone()), pane_focused,
-            panes_cmd.iter().enumerate()
-            .filter(|(n, _)| *n != pane_focused)
-            .map(|(_, el)| el.clone()).collect()
-        );
+        if pane_focused >= panes_cmd.len() {
+            return Err(Errcode::ParsingError(format!("Focused pane {} out of the {} panes described", pane_focused, panes_cmd.len())));
+        }
+        self.panes = PaneSerializer::from_cmds(panes_cmd, pane_focused);
 
         Ok(())
     }
@@ -177,54 +244,126 @@ impl TmuxWindow {
 
 
     /*          Window Configuration modifiers          */
-    pub fn set_layout(&mut self, layout: &String) -> Result<(), Errcode> {
-        let n = get_npane_from_layout(layout)?;
-        
+    pub fn set_layout(&mut self, layout: &TmuxLayout, rec: &Reconciliation) -> Result<(), Errcode> {
+        let mut layout = layout.clone();
+        if !layout.is_checksum_valid() {
+            eprintln!("Warning: invalid layout checksum {:04x}, replaced by {:04x}",
+                layout.checksum, layout.compute_checksum());
+            layout.update_checksum();
+        }
+        let n = layout.nb_panes();
+
         let npanes = self.panes.nb_panes();
-        let cmds = match n.cmp(&npanes){
-            Ordering::Less => { self.drop_cmds(npanes - n)? },
-            Ordering::Equal => { self.panes.get_panes_cmds()? },
-            Ordering::Greater => { self.new_cmds(n - npanes)? },
+        let cmds = if rec.needs_prompt(npanes, n)? {
+            match n.cmp(&npanes){
+                Ordering::Less => { self.drop_cmds(npanes - n)? },
+                Ordering::Equal => { self.panes.get_panes_cmds()? },
+                Ordering::Greater => { self.new_cmds(n - npanes)? },
+            }
+        } else {
+            rec.reconcile(self.panes.get_panes_cmds()?, n)?
         };
-        println!("Commands to set to panes: {:?}", cmds);
         self.panes.set_panes_cmds(&cmds);
 
-        self.layout = Some(layout.clone());
+        self.layout = Some(layout);
+        self.layout_preset = None;
         Ok(())
     }
 
-    pub fn drop_cmds(&mut self, ndrop: usize) -> Result<Vec<String>, Errcode> {
-        let mut cmds = self.panes.get_panes_cmds()?;
-        for _ in 0..ndrop{
-            println!("\n\nCommands in panes: ");
-            for (n, c) in cmds.iter().enumerate(){
-                println!("\t{}: {}", n, c);
-            }
-            println!("Enter the number of the command to drop: ");
-            let dropped : usize = read!();
-            let dropped_cmd = cmds.remove(dropped);
-            println!("Dropping command \"{}\"", dropped_cmd);
-            //TODO  Allow to undo drops (keep the whole history)
+    /// The layout tmuxp ends up with for the window, panes are stacked if no layout is saved
+    pub fn get_layout(&self, size: &TermSize) -> Result<TmuxLayout, Errcode> {
+        match &self.layout {
+            Some(l) => Ok(l.clone()),
+            None => LayoutTemplate::Preset(self.layout_preset.unwrap_or(LayoutPreset::EvenVertical))
+                .generate(size, self.panes.nb_panes()),
         }
-        Ok(cmds)
     }
 
-    pub fn new_cmds(&mut self, nnew: usize) -> Result<Vec<String>, Errcode> {
-        let mut cmds = self.panes.get_panes_cmds()?;
-        println!("Asking for {} new commands", nnew);
-        for _ in 0..nnew{
-            println!("\n\nCommands in panes: ");
-            for (n, c) in cmds.iter().enumerate(){
-                println!("\t{}: {}", n, c);
+    // Structural edits need a layout, generate the default one if there is none
+    fn layout_mut(&mut self, size: &TermSize) -> Result<&mut TmuxLayout, Errcode> {
+        if self.layout.is_none() {
+            self.layout = Some(self.get_layout(size)?);
+            self.layout_preset = None;
+        }
+        Ok(self.layout.as_mut().unwrap())
+    }
+
+    /// Splits the pane, the new pane is placed right after it and executes `cmd`
+    pub fn split_pane(&mut self, split: &PaneSplit, cmd: String, size: &TermSize) -> Result<(), Errcode> {
+        self.layout_mut(size)?.split_pane(split.pane, split.direction, split.percent)?;
+        self.panes.insert_pane(split.pane + 1, TmuxPane::from_cmd(cmd))
+    }
+
+    pub fn close_pane(&mut self, pane: usize, size: &TermSize) -> Result<(), Errcode> {
+        self.layout_mut(size)?.close_pane(pane)?;
+        self.panes.remove_pane(pane)
+    }
+
+    /// Inserts a pane at a position, if the window has a layout the pane before it is split
+    /// to make room for it. At position 0 the first pane is split and the new pane takes its start.
+    pub fn add_pane(&mut self, at: usize, pane: TmuxPane, direction: SplitDirection, percent: usize) -> Result<(), Errcode> {
+        // The layout is only changed if the pane can be inserted
+        let mut layout = self.layout.clone();
+        if let Some(l) = layout.as_mut() {
+            if at == 0 {
+                // The split adds its cell after the pane, the first cell is left with the new pane's part
+                l.split_pane(0, direction, 100 - percent.min(100))?;
+            } else {
+                l.split_pane(at - 1, direction, percent)?;
             }
-            println!("Enter a new command: ");
-            let cmd: String;
-            scan!("{}\n", cmd);
-            cmds.push(cmd);
-            println!("Added command \"{}\"", cmds.last().unwrap());
-            //TODO  Allow to undo adds (keep the whole history)
         }
-        Ok(cmds)
+        self.panes.insert_pane(at, pane)?;
+        self.layout = layout;
+        Ok(())
+    }
+
+    /// Removes a pane, if the window has a layout its space goes to its neighbour
+    pub fn remove_pane(&mut self, index: usize) -> Result<(), Errcode> {
+        let mut layout = self.layout.clone();
+        if let Some(l) = layout.as_mut() {
+            l.close_pane(index)?;
+        }
+        self.panes.remove_pane(index)?;
+        self.layout = layout;
+        Ok(())
+    }
+
+    pub fn swap_panes(&mut self, a: usize, b: usize) -> Result<(), Errcode> {
+        self.panes.swap_panes(a, b)
+    }
+
+    /// Moves a pane to another position, the cells of the layout stay in place
+    /// and the panes in between are shifted through them
+    pub fn move_pane(&mut self, from: usize, to: usize) -> Result<(), Errcode> {
+        self.panes.move_pane(from, to)
+    }
+
+    pub fn rotate_split(&mut self, pane: usize, size: &TermSize) -> Result<(), Errcode> {
+        self.layout_mut(size)?.rotate_split(pane)
+    }
+
+    pub fn resize_layout(&mut self, size: &TermSize) -> Result<(), Errcode> {
+        if let Some(layout) = self.layout.as_mut() {
+            layout.resize(size)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the first steps shared by the commands of all the panes to the commands run before them
+    pub fn dedupe(&mut self) -> Vec<String> {
+        let hoisted = self.panes.hoist_common_steps();
+        self.shell_command_before.extend(hoisted.iter().cloned());
+        hoisted
+    }
+
+    pub fn drop_cmds(&mut self, ndrop: usize) -> Result<Vec<String>, Errcode> {
+        let stdin = io::stdin();
+        edit_cmds(self.panes.get_panes_cmds()?, CmdEditMode::Drop(ndrop), &mut stdin.lock(), &mut io::stdout())
+    }
+
+    pub fn new_cmds(&mut self, nnew: usize) -> Result<Vec<String>, Errcode> {
+        let stdin = io::stdin();
+        edit_cmds(self.panes.get_panes_cmds()?, CmdEditMode::Add(nnew), &mut stdin.lock(), &mut io::stdout())
     }
 }
 
@@ -239,29 +378,17 @@ impl Serialize for TmuxWindow{
     where
         S: Serializer,
     {
-        let nfields = 4 + match self.layout {
-            None => 0,
-            Some(_) => 1,
-        };
-
-        let mut state = serializer.serialize_struct("TmuxWindow", nfields)?;
-        state.serialize_field("window_name", &self.window_name)?;
-        if let Some(_) = self.layout {
-            state.serialize_field("layout", &self.layout)?;
-        }
-
-        state.serialize_field("start_directory", &self.start_directory.to_str())?;
-        if self.focus {
-            state.serialize_field("focus", &self.focus.to_string())?;
+        let entries = written_entries(
+            self.entries().map_err(ser::Error::custom)?,
+            TmuxWindow::blank().entries().map_err(ser::Error::custom)?,
+            &self.unknown_keys,
+            self.written.as_ref(),
+            |key, val| self.is_written_value(key, val),
+        );
+        let mut state = serializer.serialize_map(Some(entries.len()))?;
+        for (key, val) in entries.iter() {
+            state.serialize_entry(key, val)?;
         }
-
-        state.serialize_field("panes", &self.panes)?;
-        state.serialize_field("options", &serde_json::json!(
-            {
-                "automatic-rename": if self.automatic_rename { "on" } else { "off" },
-            }
-        ))?;
-
         state.end()
     }
 }
@@ -297,18 +424,92 @@ impl<'de> Visitor<'de> for TmuxWindowVisitor {
     where
         M: MapAccess<'de>,
     {
-        let mut window = TmuxWindow::default(PathBuf::from_str("/tmp/").unwrap());
+        let mut window = TmuxWindow::blank();
+        let mut written = Map::new();
 
-        while let Some((key, value)) = access.next_entry()? {
-            if let Err(e) = window.load_entry(key, value){
-                println!("Error while deserializing TmuxWindow: {:?}", e); 
+        while let Some((key, value)) = access.next_entry::<String, Value>()? {
+            written.insert(key.clone(), value.clone());
+            if let Err(e) = window.load_entry(key.clone(), value){
+                eprintln!("Warning: invalid \"{}\" in window \"{}\", kept as it is: {}", key, window.window_name, e);
             }
         }
 
+        window.written = Some(written);
         Ok(window)
     }
 }
 
+fn index_from_json(val: &Value) -> Result<usize, Errcode> {
+    Ok(val.as_u64().ok_or_else(|| Errcode::JsonError("window_index is not a number".to_string()))? as usize)
+}
+
+/// Split of a pane requested from the commandline
+#[derive(Debug)]
+pub struct PaneSplit {
+    pub pane: usize,
+    pub direction: SplitDirection,
+    pub percent: usize,
+}
+
+impl FromStr for PaneSplit {
+    type Err = Errcode;
+
+    // Format:      PANE:h|v<:PERCENT%>
+    fn from_str(s: &str) -> Result<PaneSplit, Errcode> {
+        let invalid = || Errcode::ArgValidationError("pane split, expected PANE:h|v<:PERCENT%>");
+        let fields: Vec<&str> = s.split(WINDOWDESCR_PARSER_SEP).collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(invalid());
+        }
+        let direction = SplitDirection::from_str(fields[1])?;
+        let percent = match fields.get(2) {
+            Some(p) => usize::from_str(p.trim_end_matches('%')).map_err(|_| invalid())?,
+            None => 50,
+        };
+        if percent == 0 || percent >= 100 {
+            return Err(Errcode::ArgValidationError("pane split percentage, expected between 1% and 99%"));
+        }
+        Ok(PaneSplit { pane: usize::from_str(fields[0]).map_err(|_| invalid())?, direction, percent })
+    }
+}
+
+/// Command given to the panes from the commandline
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneCommand {
+    /// Only changes the command of this pane
+    Indexed(usize, String),
+    /// Command of the next pane, all the commands of the window are replaced
+    Positional(String),
+}
+
+impl FromStr for PaneCommand {
+    type Err = Errcode;
+
+    // Format:      PANE=CMD  |  CMD
+    fn from_str(s: &str) -> Result<PaneCommand, Errcode> {
+        if let Some((index, cmd)) = s.split_once('=') {
+            if !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()) {
+                return Ok(PaneCommand::Indexed(usize::from_str(index)?, cmd.to_string()));
+            }
+        }
+        Ok(PaneCommand::Positional(s.to_string()))
+    }
+}
+
+/// Pair of panes to swap, format:      PANE:PANE
+#[derive(Debug)]
+pub struct PaneSwap(pub usize, pub usize);
+
+impl FromStr for PaneSwap {
+    type Err = Errcode;
+
+    fn from_str(s: &str) -> Result<PaneSwap, Errcode> {
+        let invalid = || Errcode::ArgValidationError("pane swap, expected PANE:PANE");
+        let (a, b) = s.split_once(WINDOWDESCR_PARSER_SEP).ok_or_else(invalid)?;
+        Ok(PaneSwap(usize::from_str(a).map_err(|_| invalid())?, usize::from_str(b).map_err(|_| invalid())?))
+    }
+}
+
 trait TmuxWindowBuilder<V>{
     fn load_entry(&mut self, key: String, value: V) -> Result<(), Errcode>;
 }
@@ -316,83 +517,93 @@ trait TmuxWindowBuilder<V>{
 impl TmuxWindowBuilder<Value> for TmuxWindow{
     fn load_entry(&mut self, key: String, value: Value) -> Result<(), Errcode> {
         match key.as_ref() {
-            "options" => self.load_json_options(value)?,
+            "options" => self.options = TmuxOptions::from_json(OptionScope::Window, &value)?,
+            "options_after" => self.options_after = TmuxOptions::from_json(OptionScope::Window, &value)?,
+            "shell_command_before" => self.shell_command_before = commands_from_json(&value)?,
             "panes" => self.load_json_panes(value)?,
 
             "window_name" => self.window_name = strval_to_string(&value)?,
-            "layout" => self.layout = Some(strval_to_string(&value)?),
-            "focus" => self.focus = strval_to_string(&value)? == "true",
-            "start_directory" => self.start_directory = PathBuf::from(strval_to_string(&value)?),
+            "window_index" => self.window_index = Some(index_from_json(&value)?),
+            "layout" => self.load_layout(&strval_to_string(&value)?)?,
+            "focus" => self.focus = val_to_bool(&value)?,
+            "start_directory" => self.start_directory = Some(PathBuf::from(strval_to_string(&value)?)),
             
-            // TODO  Warning log here
-            _ => println!("Unknown JSON key \"{}\" for Window loading", key),
+            _ => { self.unknown_keys.insert(key, value); },
         }
         Ok(())
     }
 }
 
-pub type WindowLayout = str;
 
-pub fn get_npane_from_layout(orig_layout: &WindowLayout) -> Result<usize, Errcode> {
-    let mut layout = orig_layout.clone();
-    let mut npane = 0;
-    let mut nel = 0;
-    let mut alone = true;
-    while layout.len() > 0{
-        let (new_layout, content) = if layout.contains(","){
-            take_until(",")(layout)?
-        } else {
-            ("", layout.clone())
-        };
-        nel += 1;
-        
-        if content.contains("{") || content.contains("["){
-            if alone{
-                assert_eq!(nel, 4);
-            } else {
-                assert_eq!(nel, 3);
-            }
-            nel = 1;
-            alone = false;
-        } else if nel == 5 && !alone {
-            nel = 1;
-            npane += 1;
-        } else if content.contains("}") || content.contains("]"){
-            assert_eq!(nel, 4);
-            npane += 1;
-            nel = 0;
-        }
-
-        if new_layout.len() == 0{
-            if alone{
-                assert_eq!(nel, 5);
-                npane += 1;
-            }
-            break;
-        }
-        layout = &new_layout[1..];
-    }
-    Ok(npane)
-}
 
 
 
+#[test]
+fn test_pane_command(){
+    assert_eq!(PaneCommand::from_str("2=cargo test").unwrap(), PaneCommand::Indexed(2, "cargo test".to_string()));
+    assert_eq!(PaneCommand::from_str("0=").unwrap(), PaneCommand::Indexed(0, String::new()));
+    assert_eq!(PaneCommand::from_str("RUST_LOG=debug cargo run").unwrap(),
+        PaneCommand::Positional("RUST_LOG=debug cargo run".to_string()));
+    assert_eq!(PaneCommand::from_str("=x").unwrap(), PaneCommand::Positional("=x".to_string()));
+    assert_eq!(PaneCommand::from_str("htop").unwrap(), PaneCommand::Positional("htop".to_string()));
+}
 
+#[test]
+fn test_add_remove_panes(){
+    let mut win = TmuxWindow::default(PathBuf::from("/tmp"));
+    win.add_pane(0, TmuxPane::from_cmd("htop".to_string()), SplitDirection::Vertical, 50).unwrap();
+    assert!(win.layout.is_none());
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["htop", DEFAULT_PANE_CMD]);
+    assert_eq!(win.panes.focused_index(), 1);
+
+    win.layout = Some(TmuxLayout::from_str("c08a,100x30,0,0[100x15,0,0,0,100x14,0,16,1]").unwrap());
+    win.add_pane(2, TmuxPane::from_cmd("nvim".to_string()), SplitDirection::Horizontal, 30).unwrap();
+    let layout = win.layout.as_ref().unwrap();
+    assert_eq!(layout.nb_panes(), 3);
+    assert!(layout.is_checksum_valid());
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["htop", DEFAULT_PANE_CMD, "nvim"]);
+
+    // Nothing changes when the pane cannot be added / removed
+    assert!(win.add_pane(4, TmuxPane::from_cmd("ls".to_string()), SplitDirection::Vertical, 50).is_err());
+    assert!(win.remove_pane(3).is_err());
+    assert_eq!(win.layout.as_ref().unwrap().nb_panes(), 3);
+
+    win.remove_pane(1).unwrap();
+    assert_eq!(win.layout.as_ref().unwrap().nb_panes(), 2);
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["htop", "nvim"]);
+    assert_eq!(win.panes.focused_index(), 1);
+
+    // At position 0 the new pane gets its part of the first pane, at the top
+    win.layout = Some(TmuxLayout::from_str("c08a,100x30,0,0[100x15,0,0,0,100x14,0,16,1]").unwrap());
+    win.add_pane(0, TmuxPane::from_cmd("ls".to_string()), SplitDirection::Vertical, 20).unwrap();
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["ls", "htop", "nvim"]);
+    let layout = win.layout.as_ref().unwrap();
+    assert!(layout.is_checksum_valid());
+    assert!(layout.root.to_string().starts_with("100x30,0,0[100x3,0,0,0,100x11,0,4,"));
+
+    let layout = win.layout.clone();
+    win.move_pane(0, 2).unwrap();
+    assert_eq!(win.panes.get_panes_cmds().unwrap(), vec!["htop", "nvim", "ls"]);
+    assert_eq!(win.layout, layout);
+    assert!(win.move_pane(0, 3).is_err());
+}
 
 #[test]
-fn test_get_npane_from_layout(){
-    let test_points = vec![
-        ("5be4,211x62,0,0,15", 1),
-        ("f93e,211x62,0,0[211x31,0,0,15,211x30,0,32,24]", 2),
-        ("6669,211x62,0,0{105x62,0,0,15,105x62,106,0,25}", 2),
-        ("dcbe,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0,25}", 3),
-        ("7303,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x30,106,32,27]}", 4),
-        ("1bd3,211x62,0,0{105x62,0,0[105x31,0,0,15,105x30,0,32,26],105x62,106,0[105x31,106,0,25,105x15,106,32,27,105x14,106,48{52x14,106,48,28,26x14,159,48,29,25x14,186,48[25x7,186,48,30,25x6,186,56,31]}]}", 8)
-    ];
-
-    for (ntest, (layout, exp)) in test_points.iter().enumerate(){
-        let got = get_npane_from_layout(layout).expect("Layout parsing raised error");
-        println!("Layout {} expect {} panes, got {}", ntest, exp, got);
-        assert_eq!(got, *exp);
-    }
+fn test_layout_preset(){
+    let json = serde_json::json!({"window_name": "code", "layout": "main-vertical", "panes": ["nvim", "htop", "bash"]});
+    let mut win: TmuxWindow = serde_json::from_value(json).unwrap();
+    assert!(win.layout.is_none());
+    assert_eq!(win.layout_preset, Some(LayoutPreset::MainVertical));
+    let size = TermSize { width: 211, height: 62 };
+    assert_eq!(win.get_layout(&size).unwrap().root.to_string(),
+        "211x62,0,0{80x62,0,0,0,130x62,81,0[130x31,81,0,1,130x30,81,32,2]}");
+
+    // The preset applies to any number of panes, it is only replaced by a layout string
+    // when the splits are edited
+    win.add_pane(3, TmuxPane::from_cmd("ls".to_string()), SplitDirection::Vertical, 50).unwrap();
+    assert_eq!(serde_json::to_value(&win).unwrap()["layout"], "main-vertical");
+    win.rotate_split(0, &size).unwrap();
+    assert!(win.layout_preset.is_none());
+    assert_eq!(win.layout.as_ref().unwrap().nb_panes(), 4);
+    assert!(serde_json::to_value(&win).unwrap()["layout"].as_str().unwrap().contains("211x62,0,0"));
 }